@@ -1,836 +1,1380 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::rc::Rc;
-use std::cell::RefCell;
-use crate::lexer::{Token, TokenType, LiteralValue};
-use crate::ast::{Stmt, Expr};
-use crate::error::RuntimeError;
-use crate::builtin::*;
-
-#[derive(Debug, Clone)]
-pub enum Value {
-    Nil,
-    Boolean(bool),
-    Number(f64),
-    String(String),
-    List(Rc<RefCell<Vec<Value>>>),
-    Function(MiniScriptFunction),
-    Builtin(&'static str), // Just store function name as string for simplicity
-    FileHandle(Rc<RefCell<File>>),
-}
-
-impl PartialEq for Value {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Value::Nil, Value::Nil) => true,
-            (Value::Boolean(a), Value::Boolean(b)) => a == b,
-            (Value::Number(a), Value::Number(b)) => a == b,
-            (Value::String(a), Value::String(b)) => a == b,
-            (Value::FileHandle(_), Value::FileHandle(_)) => false, // File handles are never equal
-            _ => false,
-        }
-    }
-}
-
-pub trait Callable: std::fmt::Debug {
-    fn arity(&self) -> i32;
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError>;
-}
-
-#[derive(Debug, Clone)]
-pub struct MiniScriptFunction {
-    pub declaration: Stmt,
-    pub closure: Rc<RefCell<Environment>>,
-}
-
-impl MiniScriptFunction {
-    pub fn new(declaration: Stmt, closure: Rc<RefCell<Environment>>) -> Self {
-        Self { declaration, closure }
-    }
-}
-
-impl Callable for MiniScriptFunction {
-    fn arity(&self) -> i32 {
-        if let Stmt::Function { params, .. } = &self.declaration {
-            params.len() as i32
-        } else {
-            0
-        }
-    }
-
-    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
-        if let Stmt::Function { params, body, .. } = &self.declaration {
-            let environment = Rc::new(RefCell::new(Environment::new(Some(self.closure.clone()))));
-            
-            for (i, param) in params.iter().enumerate() {
-                environment.borrow_mut().define(&param.lexeme, arguments[i].clone());
-            }
-
-            let previous = interpreter.environment.clone();
-            interpreter.environment = environment;
-
-            let mut result = Ok(Value::Nil);
-            for statement in body {
-                match interpreter.execute(statement) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if e.message == "RETURN_VALUE" && e.return_value.is_some() {
-                            // Extract return value directly
-                            result = Ok(e.return_value.unwrap());
-                        } else {
-                            result = Err(e);
-                        }
-                        break;
-                    }
-                }
-            }
-
-            interpreter.environment = previous;
-            result
-        } else {
-            Err(RuntimeError::new(
-                "Invalid function declaration".to_string(),
-                None,
-                "<unknown>",
-            ))
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Environment {
-    values: HashMap<String, Value>,
-    enclosing: Option<Rc<RefCell<Environment>>>,
-}
-
-impl Environment {
-    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Self {
-        Self {
-            values: HashMap::new(),
-            enclosing,
-        }
-    }
-
-    pub fn define(&mut self, name: &str, value: Value) {
-        self.values.insert(name.to_string(), value);
-    }
-
-    pub fn get(&self, name_token: &Token) -> Result<Value, RuntimeError> {
-        let name = &name_token.lexeme;
-        if let Some(value) = self.values.get(name) {
-            Ok(value.clone())
-        } else if let Some(enclosing) = &self.enclosing {
-            enclosing.borrow().get(name_token)
-        } else {
-            Err(RuntimeError::new(
-                format!("Undefined variable '{}'.", name),
-                Some(name_token.line),
-                "<unknown>",
-            ))
-        }
-    }
-
-    pub fn assign(&mut self, name_token: &Token, value: Value) -> Result<(), RuntimeError> {
-        let name = &name_token.lexeme;
-        
-        // If variable exists in current scope, update it
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), value);
-            return Ok(());
-        }
-        
-        // Try to assign in enclosing scope recursively
-        if let Some(enclosing) = &self.enclosing {
-            return enclosing.borrow_mut().assign(name_token, value);
-        }
-        
-        // If not found anywhere, create in current scope (implicit declaration)
-        self.values.insert(name.to_string(), value);
-        Ok(())
-    }
-}
-
-pub struct Interpreter {
-    pub globals: Rc<RefCell<Environment>>,
-    pub environment: Rc<RefCell<Environment>>,
-    pub filename: String,
-}
-
-impl Interpreter {
-    pub fn new(filename: &str) -> Self {
-        let globals = Rc::new(RefCell::new(Environment::new(None)));
-        
-        // Define built-in functions (simplified)
-        globals.borrow_mut().define("print", Value::Builtin("print"));
-        globals.borrow_mut().define("len", Value::Builtin("len"));
-        globals.borrow_mut().define("time_now", Value::Builtin("time_now"));
-        globals.borrow_mut().define("time_format", Value::Builtin("time_format"));
-        globals.borrow_mut().define("time_parse", Value::Builtin("time_parse"));
-        globals.borrow_mut().define("time_diff", Value::Builtin("time_diff"));
-        globals.borrow_mut().define("time_year", Value::Builtin("time_year"));
-        globals.borrow_mut().define("time_month", Value::Builtin("time_month"));
-        globals.borrow_mut().define("time_day", Value::Builtin("time_day"));
-        globals.borrow_mut().define("time_hour", Value::Builtin("time_hour"));
-        globals.borrow_mut().define("time_minute", Value::Builtin("time_minute"));
-        globals.borrow_mut().define("time_second", Value::Builtin("time_second"));
-        globals.borrow_mut().define("time_weekday", Value::Builtin("time_weekday"));
-        globals.borrow_mut().define("time_add", Value::Builtin("time_add"));
-        globals.borrow_mut().define("sleep", Value::Builtin("sleep"));
-        globals.borrow_mut().define("fopen", Value::Builtin("fopen"));
-        globals.borrow_mut().define("fclose", Value::Builtin("fclose"));
-        globals.borrow_mut().define("fwrite", Value::Builtin("fwrite"));
-        globals.borrow_mut().define("fread", Value::Builtin("fread"));
-        globals.borrow_mut().define("freadline", Value::Builtin("freadline"));
-        globals.borrow_mut().define("fwriteline", Value::Builtin("fwriteline"));
-        globals.borrow_mut().define("fexists", Value::Builtin("fexists"));
-
-        let environment = globals.clone();
-
-        Self {
-            globals,
-            environment,
-            filename: filename.to_string(),
-        }
-    }
-
-    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
-        for statement in statements {
-            self.execute(statement)?;
-        }
-        Ok(())
-    }
-
-    fn execute(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
-        match stmt {
-            Stmt::Expression { expression } => {
-                self.evaluate(expression)?;
-                Ok(())
-            }
-            Stmt::Print { expressions } => {
-                let mut values = Vec::new();
-                for expr in expressions {
-                    values.push(self.evaluate(expr)?);
-                }
-                let output: Vec<String> = values.iter().map(|v| stringify_value(v)).collect();
-                println!("{}", output.join(" "));
-                Ok(())
-            }
-            Stmt::Var { name, initializer } => {
-                let value = if let Some(init) = initializer {
-                    self.evaluate(init)?
-                } else {
-                    Value::Nil
-                };
-                self.environment.borrow_mut().define(&name.lexeme, value);
-                Ok(())
-            }
-            Stmt::Block { statements } => {
-                let environment = Rc::new(RefCell::new(Environment::new(Some(self.environment.clone()))));
-                self.execute_block(statements, environment)?;
-                Ok(())
-            }
-            Stmt::Function { name, .. } => {
-                let function = MiniScriptFunction::new(stmt.clone(), self.environment.clone());
-                self.environment.borrow_mut().define(&name.lexeme, Value::Function(function));
-                Ok(())
-            }
-            Stmt::If { condition, then_branch, else_branch } => {
-                let condition_result = self.evaluate(condition)?;
-                if self.is_truthy(&condition_result) {
-                    self.execute(then_branch)?;
-                } else if let Some(else_stmt) = else_branch {
-                    self.execute(else_stmt)?;
-                }
-                Ok(())
-            }
-            Stmt::Return { value, .. } => {
-                let return_value = if let Some(val) = value {
-                    self.evaluate(val)?
-                } else {
-                    Value::Nil
-                };
-                // Use RuntimeError with return_value to properly handle all value types
-                Err(RuntimeError::with_return_value(
-                    "RETURN_VALUE".to_string(),
-                    None,
-                    &self.filename,
-                    return_value,
-                ))
-            }
-            Stmt::While { condition, body } => {
-                loop {
-                    let condition_result = self.evaluate(condition)?;
-                    if !self.is_truthy(&condition_result) {
-                        break;
-                    }
-                    self.execute(body)?;
-                }
-                Ok(())
-            }
-            Stmt::Assert { condition, message, keyword } => {
-                let condition_result = self.evaluate(condition)?;
-                if !self.is_truthy(&condition_result) {
-                    let msg_val = self.evaluate(message)?;
-                    return Err(RuntimeError::new(
-                        format!("Assertion failed: {}", stringify_value(&msg_val)),
-                        Some(keyword.line),
-                        &self.filename,
-                    ));
-                }
-                Ok(())
-            }
-            Stmt::Import { path_token, .. } => {
-                let module_path = if let Some(LiteralValue::String(path)) = &path_token.literal {
-                    path.clone()
-                } else {
-                    return Err(RuntimeError::new(
-                        "Import path must be a string".to_string(),
-                        Some(path_token.line),
-                        &self.filename,
-                    ));
-                };
-
-                let full_path = self.resolve_module_path(&module_path)?;
-                self.run_file(&full_path)?;
-                Ok(())
-            }
-        }
-    }
-
-    pub fn execute_block(&mut self, statements: &[Stmt], environment: Rc<RefCell<Environment>>) -> Result<(), RuntimeError> {
-        let previous = self.environment.clone();
-        self.environment = environment;
-
-        let mut result = Ok(());
-        for statement in statements {
-            if let Err(e) = self.execute(statement) {
-                result = Err(e);
-                break;
-            }
-        }
-
-        self.environment = previous;
-        result
-    }
-
-    fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
-        match expr {
-            Expr::Literal { value } => {
-                match value {
-                    LiteralValue::Boolean(b) => Ok(Value::Boolean(*b)),
-                    LiteralValue::Number(n) => Ok(Value::Number(*n)),
-                    LiteralValue::Integer(i) => Ok(Value::Number(*i as f64)),
-                    LiteralValue::String(s) => Ok(Value::String(s.clone())),
-                    LiteralValue::Char(c) => Ok(Value::String(c.to_string())),
-                    LiteralValue::Nil => Ok(Value::Nil),
-                }
-            }
-            Expr::ListLiteral { elements } => {
-                let mut list = Vec::new();
-                for elem in elements {
-                    list.push(self.evaluate(elem)?);
-                }
-                Ok(Value::List(Rc::new(RefCell::new(list))))
-            }
-            Expr::Variable { name } => {
-                self.environment.borrow().get(name)
-            }
-            Expr::Assign { name, value } => {
-                let val = self.evaluate(value)?;
-                self.environment.borrow_mut().assign(name, val.clone())?;
-                Ok(val)
-            }
-            Expr::Grouping { expression } => {
-                self.evaluate(expression)
-            }
-            Expr::Unary { operator, right } => {
-                let right_val = self.evaluate(right)?;
-                match operator.token_type {
-                    TokenType::Minus => {
-                        if let Value::Number(n) = right_val {
-                            Ok(Value::Number(-n))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operand must be a number.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Not => {
-                        Ok(Value::Boolean(!self.is_truthy(&right_val)))
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Unknown unary operator.".to_string(),
-                        Some(operator.line),
-                        &self.filename,
-                    ))
-                }
-            }
-            Expr::Binary { left, operator, right } => {
-                let left_val = self.evaluate(left)?;
-                let right_val = self.evaluate(right)?;
-
-                match operator.token_type {
-                    TokenType::Plus => {
-                        match (&left_val, &right_val) {
-                            (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
-                            _ => {
-                                // String concatenation
-                                let left_str = stringify_value(&left_val);
-                                let right_str = stringify_value(&right_val);
-                                Ok(Value::String(left_str + &right_str))
-                            }
-                        }
-                    }
-                    TokenType::Minus => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Number(l - r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Multiply => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Number(l * r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Divide => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            if *r == 0.0 {
-                                Err(RuntimeError::new(
-                                    "Division by zero.".to_string(),
-                                    Some(operator.line),
-                                    &self.filename,
-                                ))
-                            } else {
-                                Ok(Value::Number(l / r))
-                            }
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Greater => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Boolean(l > r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::GreaterEqual => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Boolean(l >= r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Less => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Boolean(l < r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::LessEqual => {
-                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
-                            Ok(Value::Boolean(l <= r))
-                        } else {
-                            Err(RuntimeError::new(
-                                "Operands must be numbers.".to_string(),
-                                Some(operator.line),
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    TokenType::Equal => {
-                        Ok(Value::Boolean(self.is_equal(&left_val, &right_val)))
-                    }
-                    TokenType::NotEqual => {
-                        Ok(Value::Boolean(!self.is_equal(&left_val, &right_val)))
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Unknown binary operator.".to_string(),
-                        Some(operator.line),
-                        &self.filename,
-                    ))
-                }
-            }
-            Expr::Logical { left, operator, right } => {
-                let left_val = self.evaluate(left)?;
-
-                match operator.token_type {
-                    TokenType::Or => {
-                        if self.is_truthy(&left_val) {
-                            Ok(Value::Boolean(true))
-                        } else {
-                            let right_val = self.evaluate(right)?;
-                            Ok(Value::Boolean(self.is_truthy(&right_val)))
-                        }
-                    }
-                    TokenType::And => {
-                        if !self.is_truthy(&left_val) {
-                            Ok(Value::Boolean(false))
-                        } else {
-                            let right_val = self.evaluate(right)?;
-                            Ok(Value::Boolean(self.is_truthy(&right_val)))
-                        }
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Unknown logical operator.".to_string(),
-                        Some(operator.line),
-                        &self.filename,
-                    ))
-                }
-            }
-            Expr::Call { callee, paren, arguments } => {
-                let callee_val = self.evaluate(callee)?;
-                let mut args = Vec::new();
-                for arg in arguments {
-                    args.push(self.evaluate(arg)?);
-                }
-
-                match callee_val {
-                    Value::Function(func) => {
-                        if func.arity() != -1 && args.len() != func.arity() as usize {
-                            return Err(RuntimeError::new(
-                                format!("Expected {} args but got {}.", func.arity(), args.len()),
-                                Some(paren.line),
-                                &self.filename,
-                            ));
-                        }
-                        func.call(self, args)
-                    }
-                    Value::Builtin(name) => {
-                        self.call_builtin(name, args, paren.line)
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Can only call functions and classes.".to_string(),
-                        Some(paren.line),
-                        &self.filename,
-                    ))
-                }
-            }
-            Expr::Get { object, index } => {
-                let obj_val = self.evaluate(object)?;
-                let index_val = self.evaluate(index)?;
-
-                match obj_val {
-                    Value::List(list) => {
-                        if let Value::Number(idx) = index_val {
-                            let i = idx as usize;
-                            let list_borrowed = list.borrow();
-                            if i < list_borrowed.len() {
-                                Ok(list_borrowed[i].clone())
-                            } else {
-                                Err(RuntimeError::new(
-                                    "List index out of range.".to_string(),
-                                    None,
-                                    &self.filename,
-                                ))
-                            }
-                        } else {
-                            Err(RuntimeError::new(
-                                "List index must be an integer.".to_string(),
-                                None,
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Can only index lists.".to_string(),
-                        None,
-                        &self.filename,
-                    ))
-                }
-            }
-            Expr::Set { object, index, value } => {
-                let obj_val = self.evaluate(object)?;
-                let index_val = self.evaluate(index)?;
-                let new_value = self.evaluate(value)?;
-
-                match obj_val {
-                    Value::List(list) => {
-                        if let Value::Number(idx) = index_val {
-                            let i = idx as usize;
-                            let mut list_borrowed = list.borrow_mut();
-                            if i < list_borrowed.len() {
-                                list_borrowed[i] = new_value.clone();
-                                Ok(new_value)
-                            } else {
-                                Err(RuntimeError::new(
-                                    "List index out of range.".to_string(),
-                                    None,
-                                    &self.filename,
-                                ))
-                            }
-                        } else {
-                            Err(RuntimeError::new(
-                                "List index must be an integer.".to_string(),
-                                None,
-                                &self.filename,
-                            ))
-                        }
-                    }
-                    _ => Err(RuntimeError::new(
-                        "Can only set elements of lists.".to_string(),
-                        None,
-                        &self.filename,
-                    ))
-                }
-            }
-        }
-    }
-
-    fn call_builtin(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Value, RuntimeError> {
-        match name {
-            "print" => {
-                let output: Vec<String> = args.iter().map(|v| stringify_value(v)).collect();
-                println!("{}", output.join(" "));
-                Ok(Value::Nil)
-            }
-            "len" => {
-                if args.len() != 1 {
-                    return Err(RuntimeError::new(
-                        "len() expects 1 argument.".to_string(),
-                        Some(line),
-                        &self.filename,
-                    ));
-                }
-                match &args[0] {
-                    Value::String(s) => Ok(Value::Number(s.len() as f64)),
-                    Value::List(list) => Ok(Value::Number(list.borrow().len() as f64)),
-                    _ => Err(RuntimeError::new(
-                        "len() expects a string or a list.".to_string(),
-                        Some(line),
-                        &self.filename,
-                    )),
-                }
-            }
-            "time_parse" => {
-                use crate::builtin::BuiltinTimeParse;
-                let parser = BuiltinTimeParse;
-                parser.call(self, args)
-            }
-            "time_format" => {
-                use crate::builtin::BuiltinTimeFormat;
-                let formatter = BuiltinTimeFormat;
-                formatter.call(self, args)
-            }
-            "time_now" => {
-                use crate::builtin::BuiltinTimeNow;
-                let now_fn = BuiltinTimeNow;
-                now_fn.call(self, args)
-            }
-            "time_year" => {
-                use crate::builtin::BuiltinTimeYear;
-                let year_fn = BuiltinTimeYear;
-                year_fn.call(self, args)
-            }
-            "time_month" => {
-                use crate::builtin::BuiltinTimeMonth;
-                let month_fn = BuiltinTimeMonth;
-                month_fn.call(self, args)
-            }
-            "time_day" => {
-                use crate::builtin::BuiltinTimeDay;
-                let day_fn = BuiltinTimeDay;
-                day_fn.call(self, args)
-            }
-            "time_hour" => {
-                use crate::builtin::BuiltinTimeHour;
-                let hour_fn = BuiltinTimeHour;
-                hour_fn.call(self, args)
-            }
-            "time_minute" => {
-                use crate::builtin::BuiltinTimeMinute;
-                let minute_fn = BuiltinTimeMinute;
-                minute_fn.call(self, args)
-            }
-            "time_second" => {
-                use crate::builtin::BuiltinTimeSecond;
-                let second_fn = BuiltinTimeSecond;
-                second_fn.call(self, args)
-            }
-            "time_weekday" => {
-                use crate::builtin::BuiltinTimeWeekday;
-                let weekday_fn = BuiltinTimeWeekday;
-                weekday_fn.call(self, args)
-            }
-            "time_add" => {
-                use crate::builtin::BuiltinTimeAdd;
-                let add_fn = BuiltinTimeAdd;
-                add_fn.call(self, args)
-            }
-            "time_diff" => {
-                use crate::builtin::BuiltinTimeDiff;
-                let diff_fn = BuiltinTimeDiff;
-                diff_fn.call(self, args)
-            }
-            "sleep" => {
-                use crate::builtin::BuiltinSleep;
-                let sleep_fn = BuiltinSleep;
-                sleep_fn.call(self, args)
-            }
-            "fopen" => {
-                use crate::builtin::BuiltinFOpen;
-                let open_fn = BuiltinFOpen;
-                open_fn.call(self, args)
-            }
-            "fclose" => {
-                use crate::builtin::BuiltinFClose;
-                let close_fn = BuiltinFClose;
-                close_fn.call(self, args)
-            }
-            "fwrite" => {
-                use crate::builtin::BuiltinFWrite;
-                let write_fn = BuiltinFWrite;
-                write_fn.call(self, args)
-            }
-            "fread" => {
-                use crate::builtin::BuiltinFRead;
-                let read_fn = BuiltinFRead;
-                read_fn.call(self, args)
-            }
-            "freadline" => {
-                use crate::builtin::BuiltinFReadLine;
-                let readline_fn = BuiltinFReadLine;
-                readline_fn.call(self, args)
-            }
-            "fwriteline" => {
-                use crate::builtin::BuiltinFWriteLine;
-                let writeline_fn = BuiltinFWriteLine;
-                writeline_fn.call(self, args)
-            }
-            "fexists" => {
-                use crate::builtin::BuiltinFExists;
-                let exists_fn = BuiltinFExists;
-                exists_fn.call(self, args)
-            }
-            _ => {
-                // For now, just return nil for unimplemented built-ins
-                Ok(Value::Nil)
-            }
-        }
-    }
-
-    fn is_truthy(&self, value: &Value) -> bool {
-        match value {
-            Value::Nil => false,
-            Value::Boolean(b) => *b,
-            Value::Number(n) => *n != 0.0,
-            _ => true,
-        }
-    }
-
-    fn is_equal(&self, a: &Value, b: &Value) -> bool {
-        a == b
-    }
-
-    fn value_to_string(&self, value: &Value) -> String {
-        match value {
-            Value::String(s) => format!("\"{}\"", s),
-            Value::Number(n) => n.to_string(),
-            Value::Boolean(b) => b.to_string(),
-            Value::Nil => "nil".to_string(),
-            _ => "complex".to_string(),
-        }
-    }
-
-    pub fn parse_return_value(&self, s: &str) -> Value {
-        if s == "nil" {
-            Value::Nil
-        } else if s == "true" {
-            Value::Boolean(true)
-        } else if s == "false" {
-            Value::Boolean(false)
-        } else if s.starts_with('"') && s.ends_with('"') {
-            Value::String(s[1..s.len()-1].to_string())
-        } else if let Ok(n) = s.parse::<f64>() {
-            Value::Number(n)
-        } else {
-            Value::Nil
-        }
-    }
-
-    fn resolve_module_path(&self, module_path: &str) -> Result<String, RuntimeError> {
-        let mut search_paths = Vec::new();
-
-        // 1. Path relative to the current script file
-        if self.filename != "<REPL>" && self.filename != "<unknown>" {
-            if let Some(parent) = std::path::Path::new(&self.filename).parent() {
-                search_paths.push(parent.to_path_buf());
-            }
-        }
-
-        // 2. Current working directory
-        if let Ok(cwd) = std::env::current_dir() {
-            search_paths.push(cwd);
-        }
-
-        // 3. MODULESPATH environment variable
-        if let Ok(modules_path) = std::env::var("MODULESPATH") {
-            for path in modules_path.split(';') {
-                search_paths.push(std::path::PathBuf::from(path));
-            }
-        }
-
-        for base_dir in search_paths {
-            // Try the path as is
-            let test_path = base_dir.join(module_path);
-            if test_path.is_file() {
-                return Ok(test_path.to_string_lossy().to_string());
-            }
-
-            // Try adding .ms extension
-            if !module_path.ends_with(".ms") {
-                let test_path_ext = base_dir.join(format!("{}.ms", module_path));
-                if test_path_ext.is_file() {
-                    return Ok(test_path_ext.to_string_lossy().to_string());
-                }
-            }
-        }
-
-        Err(RuntimeError::new(
-            format!("Cannot find module: {}", module_path),
-            None,
-            &self.filename,
-        ))
-    }
-
-    fn run_file(&mut self, path: &str) -> Result<(), RuntimeError> {
-        let source = std::fs::read_to_string(path)
-            .map_err(|_| RuntimeError::new(
-                format!("Could not read file: {}", path),
-                None,
-                &self.filename,
-            ))?;
-
-        crate::run(&source, path, Some(self))
-    }
-}
+use std::collections::HashMap;
+use std::fs::File;
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::lexer::{Token, TokenType, LiteralValue, Span};
+use crate::ast::{Stmt, Expr};
+use crate::error::RuntimeError;
+use crate::builtin::*;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    List(Rc<RefCell<Vec<Value>>>),
+    // Boxed because `MiniScriptFunction` embeds a whole `Stmt` by value,
+    // which would otherwise make every `Value` as wide as the largest
+    // `Stmt` variant even when it's holding a plain number or nil.
+    Function(Box<MiniScriptFunction>),
+    Builtin(String),
+    FileHandle(Rc<RefCell<File>>),
+    Iterator(LazyIterator),
+    Struct { type_name: String, fields: Rc<RefCell<HashMap<String, Value>>> },
+    Type(String),
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::FileHandle(_), Value::FileHandle(_)) => false, // File handles are never equal
+            (Value::Iterator(_), Value::Iterator(_)) => false, // Iterators are never equal
+            (Value::Struct { type_name: t1, fields: f1 }, Value::Struct { type_name: t2, fields: f2 }) => {
+                t1 == t2 && *f1.borrow() == *f2.borrow()
+            }
+            (Value::Type(a), Value::Type(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// A lazily-pulled, stateful sequence of values. Each pull may need to call back
+/// into the interpreter (e.g. to apply a mapper/predicate function), so the
+/// closure takes the interpreter by reference rather than capturing it.
+pub type LazyIterFn = dyn FnMut(&mut Interpreter) -> Option<Result<Value, RuntimeError>>;
+
+#[derive(Clone)]
+pub struct LazyIterator(pub Rc<RefCell<LazyIterFn>>);
+
+impl LazyIterator {
+    pub fn new(f: impl FnMut(&mut Interpreter) -> Option<Result<Value, RuntimeError>> + 'static) -> Self {
+        Self(Rc::new(RefCell::new(f)))
+    }
+
+    pub fn next(&self, interpreter: &mut Interpreter) -> Option<Result<Value, RuntimeError>> {
+        (self.0.borrow_mut())(interpreter)
+    }
+}
+
+impl std::fmt::Debug for LazyIterator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<iterator>")
+    }
+}
+
+pub trait Callable: std::fmt::Debug {
+    fn arity(&self) -> i32;
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError>;
+}
+
+/// `range(n)`: a lazy counter from 0 up to (but not including) `n`.
+#[derive(Debug, Clone)]
+struct BuiltinRange;
+
+impl Callable for BuiltinRange {
+    fn arity(&self) -> i32 {
+        1
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let limit = match &arguments[0] {
+            Value::Number(n) => *n as i64,
+            _ => return Err(RuntimeError::new(
+                "range() expects a numeric argument.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        let mut current = 0i64;
+        Ok(Value::Iterator(LazyIterator::new(move |_interp| {
+            if current < limit {
+                let v = Value::Number(current as f64);
+                current += 1;
+                Some(Ok(v))
+            } else {
+                None
+            }
+        })))
+    }
+}
+
+/// `collect(it)` / `to_list(it)`: drains an iterator (or copies a list) into a `Value::List`.
+#[derive(Debug, Clone)]
+struct BuiltinCollect;
+
+impl Callable for BuiltinCollect {
+    fn arity(&self) -> i32 {
+        1
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::Iterator(it) => {
+                let mut result = Vec::new();
+                loop {
+                    match it.next(interpreter) {
+                        Some(Ok(v)) => result.push(v),
+                        Some(Err(e)) => return Err(e),
+                        None => break,
+                    }
+                }
+                Ok(Value::List(Rc::new(RefCell::new(result))))
+            }
+            Value::List(list) => Ok(Value::List(Rc::new(RefCell::new(list.borrow().clone())))),
+            _ => Err(RuntimeError::new(
+                "collect() expects an iterator or list.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        }
+    }
+}
+
+/// `input()`: reads one line from stdin.
+#[derive(Debug, Clone)]
+struct BuiltinInput;
+
+impl Callable for BuiltinInput {
+    fn arity(&self) -> i32 {
+        0
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut entered = String::new();
+        std::io::stdin().read_line(&mut entered).map_err(|e| RuntimeError::new(
+            format!("Failed to read from stdin: {}", e),
+            None,
+            "<builtin>",
+        ))?;
+        Ok(Value::String(entered.trim_end_matches(['\n', '\r']).to_string()))
+    }
+}
+
+/// `chr(n)`: a numeric code point to a single-character string.
+#[derive(Debug, Clone)]
+struct BuiltinChr;
+
+impl Callable for BuiltinChr {
+    fn arity(&self) -> i32 {
+        1
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::Number(n) => {
+                let code = *n as u32;
+                match char::from_u32(code) {
+                    Some(c) => Ok(Value::String(c.to_string())),
+                    None => Err(RuntimeError::new(
+                        format!("{} is not a valid character code.", code),
+                        None,
+                        "<builtin>",
+                    )),
+                }
+            }
+            _ => Err(RuntimeError::new(
+                "chr() expects a numeric argument.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        }
+    }
+}
+
+/// `ord(s)`: the first character of a string to its numeric code point.
+#[derive(Debug, Clone)]
+struct BuiltinOrd;
+
+impl Callable for BuiltinOrd {
+    fn arity(&self) -> i32 {
+        1
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        match &arguments[0] {
+            Value::String(s) => match s.chars().next() {
+                Some(c) => Ok(Value::Number(c as u32 as f64)),
+                None => Err(RuntimeError::new(
+                    "ord() expects a non-empty string.".to_string(),
+                    None,
+                    "<builtin>",
+                )),
+            },
+            _ => Err(RuntimeError::new(
+                "ord() expects a string argument.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        }
+    }
+}
+
+/// Non-local control flow produced while executing statements/expressions.
+/// Replaces the old sentinel-message `RuntimeError("RETURN_VALUE")` hack.
+pub enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(e: RuntimeError) -> Self {
+        Unwind::Error(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MiniScriptFunction {
+    pub declaration: Stmt,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl MiniScriptFunction {
+    pub fn new(declaration: Stmt, closure: Rc<RefCell<Environment>>) -> Self {
+        Self { declaration, closure }
+    }
+}
+
+impl Callable for MiniScriptFunction {
+    fn arity(&self) -> i32 {
+        if let Stmt::Function { params, .. } = &self.declaration {
+            params.len() as i32
+        } else {
+            0
+        }
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        if let Stmt::Function { params, body, .. } = &self.declaration {
+            let environment = Rc::new(RefCell::new(Environment::new(Some(self.closure.clone()))));
+            
+            for (i, param) in params.iter().enumerate() {
+                environment.borrow_mut().define(&param.lexeme, arguments[i].clone());
+            }
+
+            let previous = interpreter.environment.clone();
+            interpreter.environment = environment;
+
+            let mut result = Ok(Value::Nil);
+            for statement in body {
+                match interpreter.execute(statement) {
+                    Ok(_) => {}
+                    Err(Unwind::Return(value)) => {
+                        result = Ok(value);
+                        break;
+                    }
+                    Err(Unwind::Error(e)) => {
+                        result = Err(e);
+                        break;
+                    }
+                    Err(Unwind::Break) | Err(Unwind::Continue) => {
+                        result = Err(RuntimeError::new(
+                            "'break'/'continue' outside of a loop.".to_string(),
+                            None,
+                            &interpreter.filename,
+                        ));
+                        break;
+                    }
+                }
+            }
+
+            interpreter.environment = previous;
+            result
+        } else {
+            Err(RuntimeError::new(
+                "Invalid function declaration".to_string(),
+                None,
+                "<unknown>",
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing,
+        }
+    }
+
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Returns a copy of the bindings declared directly in this scope
+    /// (not its enclosing scopes), used to capture a module's exports.
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        self.values.clone()
+    }
+
+    pub fn get(&self, name_token: &Token) -> Result<Value, RuntimeError> {
+        let name = &name_token.lexeme;
+        if let Some(value) = self.values.get(name) {
+            Ok(value.clone())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get(name_token)
+        } else {
+            Err(RuntimeError::new(
+                format!("Undefined variable '{}'.", name),
+                Some(name_token.line),
+                "<unknown>",
+            ))
+        }
+    }
+
+    pub fn assign(&mut self, name_token: &Token, value: Value) -> Result<(), RuntimeError> {
+        let name = &name_token.lexeme;
+
+        // If variable exists in current scope, update it
+        if self.values.contains_key(name) {
+            self.values.insert(name.to_string(), value);
+            return Ok(());
+        }
+
+        // Try to assign in enclosing scope recursively
+        if let Some(enclosing) = &self.enclosing {
+            return enclosing.borrow_mut().assign(name_token, value);
+        }
+
+        // If not found anywhere, create in current scope (implicit declaration)
+        self.values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// Reads `name` directly out of the scope `distance` hops up from this
+    /// one, as computed by the resolver, instead of searching the chain.
+    pub fn get_at(&self, distance: usize, name_token: &Token) -> Result<Value, RuntimeError> {
+        if distance == 0 {
+            self.values.get(&name_token.lexeme).cloned().ok_or_else(|| RuntimeError::new(
+                format!("Undefined variable '{}'.", name_token.lexeme),
+                Some(name_token.line),
+                "<unknown>",
+            ))
+        } else {
+            let enclosing = self.enclosing.as_ref().ok_or_else(|| RuntimeError::new(
+                format!("Undefined variable '{}'.", name_token.lexeme),
+                Some(name_token.line),
+                "<unknown>",
+            ))?;
+            enclosing.borrow().get_at(distance - 1, name_token)
+        }
+    }
+
+    /// Writes `name` directly into the scope `distance` hops up from this
+    /// one, mirroring `get_at`.
+    pub fn assign_at(&mut self, distance: usize, name_token: &Token, value: Value) -> Result<(), RuntimeError> {
+        if distance == 0 {
+            self.values.insert(name_token.lexeme.clone(), value);
+            Ok(())
+        } else {
+            let enclosing = self.enclosing.as_ref().ok_or_else(|| RuntimeError::new(
+                format!("Undefined variable '{}'.", name_token.lexeme),
+                Some(name_token.line),
+                "<unknown>",
+            ))?;
+            enclosing.borrow_mut().assign_at(distance - 1, name_token, value)
+        }
+    }
+}
+
+pub struct Interpreter {
+    pub globals: Rc<RefCell<Environment>>,
+    pub environment: Rc<RefCell<Environment>>,
+    pub filename: String,
+    /// Declared field names (in declaration order) for each `struct` type, keyed by type name.
+    struct_defs: HashMap<String, Vec<String>>,
+    /// Bindings exported by an already-run module, keyed by its canonicalized path,
+    /// so importing the same file twice splices in the cached bindings instead of
+    /// re-running its source.
+    module_cache: HashMap<String, HashMap<String, Value>>,
+    /// Native functions reachable by name, keyed the same way as `Value::Builtin`.
+    /// Hosts embedding the interpreter can add their own via `register_builtin`.
+    builtins: HashMap<String, Rc<dyn Callable>>,
+    /// Canonical paths of modules currently being loaded, used to detect import cycles.
+    import_stack: Vec<String>,
+    /// Directed import graph: a module's path mapped to the paths it imports.
+    import_graph: HashMap<String, Vec<String>>,
+}
+
+impl Interpreter {
+    pub fn new(filename: &str) -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new(None)));
+        let environment = globals.clone();
+
+        let mut interpreter = Self {
+            globals,
+            environment,
+            filename: filename.to_string(),
+            struct_defs: HashMap::new(),
+            module_cache: HashMap::new(),
+            builtins: HashMap::new(),
+            import_stack: Vec::new(),
+            import_graph: HashMap::new(),
+        };
+
+        interpreter.register_builtin("print", BuiltinPrint);
+        interpreter.register_builtin("len", BuiltinLen);
+        interpreter.register_builtin("time_now", BuiltinTimeNow);
+        interpreter.register_builtin("time_format", BuiltinTimeFormat);
+        interpreter.register_builtin("time_parse", BuiltinTimeParse);
+        interpreter.register_builtin("time_diff", BuiltinTimeDiff);
+        interpreter.register_builtin("time_year", BuiltinTimeYear);
+        interpreter.register_builtin("time_month", BuiltinTimeMonth);
+        interpreter.register_builtin("time_day", BuiltinTimeDay);
+        interpreter.register_builtin("time_hour", BuiltinTimeHour);
+        interpreter.register_builtin("time_minute", BuiltinTimeMinute);
+        interpreter.register_builtin("time_second", BuiltinTimeSecond);
+        interpreter.register_builtin("time_weekday", BuiltinTimeWeekday);
+        interpreter.register_builtin("time_add", BuiltinTimeAdd);
+        interpreter.register_builtin("time_start_of_week", BuiltinTimeStartOfWeek);
+        interpreter.register_builtin("time_add_days", BuiltinTimeAddDays);
+        interpreter.register_builtin("time_duration_days", BuiltinTimeDurationDays);
+        interpreter.register_builtin("sleep", BuiltinSleep);
+        interpreter.register_builtin("fopen", BuiltinFOpen);
+        interpreter.register_builtin("fclose", BuiltinFClose);
+        interpreter.register_builtin("fwrite", BuiltinFWrite);
+        interpreter.register_builtin("fread", BuiltinFRead);
+        interpreter.register_builtin("freadline", BuiltinFReadLine);
+        interpreter.register_builtin("fwriteline", BuiltinFWriteLine);
+        interpreter.register_builtin("fexists", BuiltinFExists);
+        interpreter.register_builtin("range", BuiltinRange);
+        interpreter.register_builtin("collect", BuiltinCollect);
+        interpreter.register_builtin("to_list", BuiltinCollect);
+        interpreter.register_builtin("input", BuiltinInput);
+        interpreter.register_builtin("chr", BuiltinChr);
+        interpreter.register_builtin("ord", BuiltinOrd);
+
+        interpreter
+    }
+
+    /// Registers a native function under `name`, making it callable from
+    /// scripts as `Value::Builtin(name)` and overriding any existing builtin
+    /// of the same name. Host code embedding the interpreter uses this to
+    /// add its own functions without touching `call_builtin`.
+    pub fn register_builtin(&mut self, name: &str, f: impl Callable + 'static) {
+        self.globals.borrow_mut().define(name, Value::Builtin(name.to_string()));
+        self.builtins.insert(name.to_string(), Rc::new(f));
+    }
+
+    /// Forgets every cached module's exports, so the next `import` of any
+    /// module re-runs its source. Useful for a REPL or long-running host
+    /// that wants to pick up edited module files. Not called by this
+    /// binary itself; kept for hosts embedding the interpreter.
+    #[allow(dead_code)]
+    pub fn clear_module_cache(&mut self) {
+        self.module_cache.clear();
+    }
+
+    /// Forgets the cached exports for a single module, identified the same
+    /// way an import path would be (resolved and canonicalized). Not called
+    /// by this binary itself; kept for hosts embedding the interpreter.
+    #[allow(dead_code)]
+    pub fn clear_module_cache_for_path(&mut self, path: &str) {
+        let key = self.resolve_module_path(path).unwrap_or_else(|_| path.to_string());
+        self.module_cache.remove(&key);
+    }
+
+    /// Returns the import dependency graph built so far: each entry maps a
+    /// module's canonical path to the canonical paths it has imported.
+    /// Intended for tooling (e.g. printing a dependency tree), not the
+    /// interpreter itself.
+    pub fn module_dependencies(&self) -> &HashMap<String, Vec<String>> {
+        &self.import_graph
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            let result = self.execute(statement);
+            self.finish(result)?;
+        }
+        Ok(())
+    }
+
+    /// Like `interpret`, but a bare expression statement has its value echoed
+    /// via `stringify_value` instead of being discarded, so the REPL behaves
+    /// like a calculator. Everything else executes exactly as it would in a
+    /// normal script.
+    pub fn interpret_repl(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            let result = match statement {
+                Stmt::Expression { expression } => self.evaluate(expression).map(|value| {
+                    if !matches!(value, Value::Nil) {
+                        println!("{}", stringify_value(&value));
+                    }
+                }),
+                other => self.execute(other),
+            };
+            self.finish(result)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&self, result: Result<(), Unwind>) -> Result<(), RuntimeError> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(Unwind::Error(e)) => Err(e),
+            Err(Unwind::Return(_)) => Err(RuntimeError::new(
+                "'return' outside of a function.".to_string(),
+                None,
+                &self.filename,
+            )),
+            Err(Unwind::Break) | Err(Unwind::Continue) => Err(RuntimeError::new(
+                "'break'/'continue' outside of a loop.".to_string(),
+                None,
+                &self.filename,
+            )),
+        }
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), Unwind> {
+        match stmt {
+            Stmt::Expression { expression } => {
+                self.evaluate(expression)?;
+                Ok(())
+            }
+            Stmt::Print { expressions } => {
+                let mut values = Vec::new();
+                for expr in expressions {
+                    values.push(self.evaluate(expr)?);
+                }
+                let output: Vec<String> = values.iter().map(stringify_value).collect();
+                println!("{}", output.join(" "));
+                Ok(())
+            }
+            Stmt::Var { name, initializer } => {
+                let value = if let Some(init) = initializer {
+                    self.evaluate(init)?
+                } else {
+                    Value::Nil
+                };
+                self.environment.borrow_mut().define(&name.lexeme, value);
+                Ok(())
+            }
+            Stmt::Block { statements } => {
+                let environment = Rc::new(RefCell::new(Environment::new(Some(self.environment.clone()))));
+                self.execute_block(statements, environment)?;
+                Ok(())
+            }
+            Stmt::Function { name, .. } => {
+                let function = MiniScriptFunction::new(stmt.clone(), self.environment.clone());
+                self.environment.borrow_mut().define(&name.lexeme, Value::Function(Box::new(function)));
+                Ok(())
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                let condition_result = self.evaluate(condition)?;
+                if self.is_truthy(&condition_result) {
+                    self.execute(then_branch)?;
+                } else if let Some(else_stmt) = else_branch {
+                    self.execute(else_stmt)?;
+                }
+                Ok(())
+            }
+            Stmt::Return { value, .. } => {
+                let return_value = if let Some(val) = value {
+                    self.evaluate(val)?
+                } else {
+                    Value::Nil
+                };
+                Err(Unwind::Return(return_value))
+            }
+            Stmt::While { condition, body } => {
+                loop {
+                    let condition_result = self.evaluate(condition)?;
+                    if !self.is_truthy(&condition_result) {
+                        break;
+                    }
+                    match self.execute(body) {
+                        Ok(()) => {}
+                        Err(Unwind::Break) => break,
+                        Err(Unwind::Continue) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(())
+            }
+            Stmt::DoWhile { body, condition } => {
+                loop {
+                    match self.execute(body) {
+                        Ok(()) => {}
+                        Err(Unwind::Break) => break,
+                        Err(Unwind::Continue) => {}
+                        Err(e) => return Err(e),
+                    }
+                    let condition_result = self.evaluate(condition)?;
+                    if !self.is_truthy(&condition_result) {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Stmt::Loop { body } => {
+                loop {
+                    match self.execute(body) {
+                        Ok(()) => {}
+                        Err(Unwind::Break) => break,
+                        Err(Unwind::Continue) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(())
+            }
+            Stmt::Assert { condition, message, keyword } => {
+                let condition_result = self.evaluate(condition)?;
+                if !self.is_truthy(&condition_result) {
+                    let msg_val = self.evaluate(message)?;
+                    return Err(RuntimeError::new(
+                        format!("Assertion failed: {}", stringify_value(&msg_val)),
+                        Some(keyword.line),
+                        &self.filename,
+                    ).into());
+                }
+                Ok(())
+            }
+            Stmt::Import { path_token, .. } => {
+                let module_path = if let Some(LiteralValue::String(path)) = &path_token.literal {
+                    path.clone()
+                } else {
+                    return Err(RuntimeError::new(
+                        "Import path must be a string".to_string(),
+                        Some(path_token.line),
+                        &self.filename,
+                    ).into());
+                };
+
+                let full_path = self.resolve_module_path(&module_path)?;
+                self.run_file(&full_path)?;
+                Ok(())
+            }
+            Stmt::Break { .. } => Err(Unwind::Break),
+            Stmt::Continue { .. } => Err(Unwind::Continue),
+            Stmt::Struct { name, fields } => {
+                let field_names: Vec<String> = fields.iter().map(|f| f.lexeme.clone()).collect();
+                self.struct_defs.insert(name.lexeme.clone(), field_names);
+                self.environment.borrow_mut().define(&name.lexeme, Value::Type(name.lexeme.clone()));
+                Ok(())
+            }
+            Stmt::For { var, iterable, body } => {
+                let iterable_val = self.evaluate(iterable)?;
+                let iterator = self.to_iterator(iterable_val, var.line)?;
+
+                while let Some(item) = iterator.next(self) {
+                    let item = item.map_err(Unwind::Error)?;
+                    let environment = Rc::new(RefCell::new(Environment::new(Some(self.environment.clone()))));
+                    environment.borrow_mut().define(&var.lexeme, item);
+
+                    let previous = self.environment.clone();
+                    self.environment = environment;
+                    let result = self.execute(body);
+                    self.environment = previous;
+
+                    match result {
+                        Ok(()) => {}
+                        Err(Unwind::Break) => break,
+                        Err(Unwind::Continue) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn execute_block(&mut self, statements: &[Stmt], environment: Rc<RefCell<Environment>>) -> Result<(), Unwind> {
+        let previous = self.environment.clone();
+        self.environment = environment;
+
+        let mut result = Ok(());
+        for statement in statements {
+            if let Err(e) = self.execute(statement) {
+                result = Err(e);
+                break;
+            }
+        }
+
+        self.environment = previous;
+        result
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<Value, Unwind> {
+        match expr {
+            Expr::Literal { value } => {
+                match value {
+                    LiteralValue::Boolean(b) => Ok(Value::Boolean(*b)),
+                    LiteralValue::Number(n) => Ok(Value::Number(*n)),
+                    LiteralValue::Integer(i) => Ok(Value::Number(*i as f64)),
+                    LiteralValue::String(s) => Ok(Value::String(s.clone())),
+                    LiteralValue::Char(c) => Ok(Value::String(c.to_string())),
+                    LiteralValue::Nil => Ok(Value::Nil),
+                }
+            }
+            Expr::ListLiteral { elements } => {
+                let mut list = Vec::new();
+                for elem in elements {
+                    list.push(self.evaluate(elem)?);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(list))))
+            }
+            Expr::Variable { name, depth } => {
+                self.lookup_variable(name, *depth).map_err(Unwind::Error)
+            }
+            Expr::Assign { name, value, operator, depth } => {
+                let val = self.evaluate(value)?;
+                let final_val = if let Some(op) = operator {
+                    let current = self.lookup_variable(name, *depth).map_err(Unwind::Error)?;
+                    self.combine_for_assign(op, current, val).map_err(Unwind::Error)?
+                } else {
+                    val
+                };
+                match depth {
+                    Some(distance) => self.environment.borrow_mut().assign_at(*distance, name, final_val.clone())?,
+                    None => self.environment.borrow_mut().assign(name, final_val.clone())?,
+                }
+                Ok(final_val)
+            }
+            Expr::Grouping { expression } => {
+                self.evaluate(expression)
+            }
+            Expr::Lambda { params, body } => {
+                let name = Token::new(TokenType::Identifier, "<lambda>".to_string(), None, 0, Span::synthetic(&self.filename));
+                let declaration = Stmt::Function { name, params: params.clone(), body: body.clone() };
+                let function = MiniScriptFunction::new(declaration, self.environment.clone());
+                Ok(Value::Function(Box::new(function)))
+            }
+            Expr::Unary { operator, right } => {
+                let right_val = self.evaluate(right)?;
+                match operator.token_type {
+                    TokenType::Minus => {
+                        if let Value::Number(n) = right_val {
+                            Ok(Value::Number(-n))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operand must be a number.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Not => {
+                        Ok(Value::Boolean(!self.is_truthy(&right_val)))
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Unknown unary operator.".to_string(),
+                        Some(operator.line),
+                        &self.filename,
+                    ).into())
+                }
+            }
+            Expr::Binary { left, operator, right } => {
+                let left_val = self.evaluate(left)?;
+                let right_val = self.evaluate(right)?;
+
+                match operator.token_type {
+                    TokenType::Plus => {
+                        match (&left_val, &right_val) {
+                            (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+                            _ => {
+                                // String concatenation
+                                let left_str = stringify_value(&left_val);
+                                let right_str = stringify_value(&right_val);
+                                Ok(Value::String(left_str + &right_str))
+                            }
+                        }
+                    }
+                    TokenType::Minus => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Number(l - r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Multiply => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Number(l * r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Divide => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            if *r == 0.0 {
+                                Err(RuntimeError::new(
+                                    "Division by zero.".to_string(),
+                                    Some(operator.line),
+                                    &self.filename,
+                                ).into())
+                            } else {
+                                Ok(Value::Number(l / r))
+                            }
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Greater => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Boolean(l > r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::GreaterEqual => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Boolean(l >= r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Less => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Boolean(l < r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::LessEqual => {
+                        if let (Value::Number(l), Value::Number(r)) = (&left_val, &right_val) {
+                            Ok(Value::Boolean(l <= r))
+                        } else {
+                            Err(RuntimeError::new(
+                                "Operands must be numbers.".to_string(),
+                                Some(operator.line),
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    TokenType::Equal => {
+                        Ok(Value::Boolean(self.is_equal(&left_val, &right_val)))
+                    }
+                    TokenType::NotEqual => {
+                        Ok(Value::Boolean(!self.is_equal(&left_val, &right_val)))
+                    }
+                    TokenType::PipeMap => {
+                        let source = self.to_iterator(left_val, operator.line)?;
+                        let mapper = right_val;
+                        Ok(Value::Iterator(LazyIterator::new(move |interp| {
+                            match source.next(interp) {
+                                Some(Ok(v)) => Some(interp.call_value(mapper.clone(), vec![v], 0)),
+                                Some(Err(e)) => Some(Err(e)),
+                                None => None,
+                            }
+                        })))
+                    }
+                    TokenType::PipeFilter => {
+                        let source = self.to_iterator(left_val, operator.line)?;
+                        let predicate = right_val;
+                        Ok(Value::Iterator(LazyIterator::new(move |interp| {
+                            loop {
+                                match source.next(interp) {
+                                    Some(Ok(v)) => {
+                                        match interp.call_value(predicate.clone(), vec![v.clone()], 0) {
+                                            Ok(result) => {
+                                                if interp.is_truthy(&result) {
+                                                    return Some(Ok(v));
+                                                }
+                                            }
+                                            Err(e) => return Some(Err(e)),
+                                        }
+                                    }
+                                    Some(Err(e)) => return Some(Err(e)),
+                                    None => return None,
+                                }
+                            }
+                        })))
+                    }
+                    TokenType::PipeApply => {
+                        let source = self.to_iterator(left_val, operator.line)?;
+                        self.call_value(right_val, vec![Value::Iterator(source)], operator.line).map_err(Unwind::Error)
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Unknown binary operator.".to_string(),
+                        Some(operator.line),
+                        &self.filename,
+                    ).into())
+                }
+            }
+            Expr::Logical { left, operator, right } => {
+                let left_val = self.evaluate(left)?;
+
+                match operator.token_type {
+                    TokenType::Or => {
+                        if self.is_truthy(&left_val) {
+                            Ok(Value::Boolean(true))
+                        } else {
+                            let right_val = self.evaluate(right)?;
+                            Ok(Value::Boolean(self.is_truthy(&right_val)))
+                        }
+                    }
+                    TokenType::And => {
+                        if !self.is_truthy(&left_val) {
+                            Ok(Value::Boolean(false))
+                        } else {
+                            let right_val = self.evaluate(right)?;
+                            Ok(Value::Boolean(self.is_truthy(&right_val)))
+                        }
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Unknown logical operator.".to_string(),
+                        Some(operator.line),
+                        &self.filename,
+                    ).into())
+                }
+            }
+            Expr::Call { callee, paren, arguments } => {
+                let callee_val = self.evaluate(callee)?;
+                let mut args = Vec::new();
+                for arg in arguments {
+                    args.push(self.evaluate(arg)?);
+                }
+
+                self.call_value(callee_val, args, paren.line).map_err(Unwind::Error)
+            }
+            Expr::Get { object, index } => {
+                let obj_val = self.evaluate(object)?;
+                let index_val = self.evaluate(index)?;
+
+                match obj_val {
+                    Value::List(list) => {
+                        if let Value::Number(idx) = index_val {
+                            let i = idx as usize;
+                            let list_borrowed = list.borrow();
+                            if i < list_borrowed.len() {
+                                Ok(list_borrowed[i].clone())
+                            } else {
+                                Err(RuntimeError::new(
+                                    "List index out of range.".to_string(),
+                                    None,
+                                    &self.filename,
+                                ).into())
+                            }
+                        } else {
+                            Err(RuntimeError::new(
+                                "List index must be an integer.".to_string(),
+                                None,
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Can only index lists.".to_string(),
+                        None,
+                        &self.filename,
+                    ).into())
+                }
+            }
+            // `object` and `index` are each evaluated exactly once here, even for
+            // a compound `target[index] += value`: `combine_for_assign` below
+            // reuses the value already read out of the list/struct rather than
+            // re-evaluating `index`.
+            Expr::Set { object, index, value, operator } => {
+                let obj_val = self.evaluate(object)?;
+                let index_val = self.evaluate(index)?;
+                let new_value = self.evaluate(value)?;
+
+                match obj_val {
+                    Value::List(list) => {
+                        if let Value::Number(idx) = index_val {
+                            let i = idx as usize;
+                            let mut list_borrowed = list.borrow_mut();
+                            if i < list_borrowed.len() {
+                                let to_store = if let Some(op) = operator {
+                                    self.combine_for_assign(op, list_borrowed[i].clone(), new_value).map_err(Unwind::Error)?
+                                } else {
+                                    new_value
+                                };
+                                list_borrowed[i] = to_store.clone();
+                                Ok(to_store)
+                            } else {
+                                Err(RuntimeError::new(
+                                    "List index out of range.".to_string(),
+                                    None,
+                                    &self.filename,
+                                ).into())
+                            }
+                        } else {
+                            Err(RuntimeError::new(
+                                "List index must be an integer.".to_string(),
+                                None,
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    Value::Struct { fields, .. } => {
+                        if let Value::String(field_name) = index_val {
+                            let mut fields_borrowed = fields.borrow_mut();
+                            let to_store = if let Some(op) = operator {
+                                let current = fields_borrowed.get(&field_name).cloned().unwrap_or(Value::Nil);
+                                self.combine_for_assign(op, current, new_value).map_err(Unwind::Error)?
+                            } else {
+                                new_value
+                            };
+                            fields_borrowed.insert(field_name, to_store.clone());
+                            Ok(to_store)
+                        } else {
+                            Err(RuntimeError::new(
+                                "Field name must be a string.".to_string(),
+                                None,
+                                &self.filename,
+                            ).into())
+                        }
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Can only set elements of lists or fields of structs.".to_string(),
+                        None,
+                        &self.filename,
+                    ).into())
+                }
+            }
+            Expr::Field { object, name } => {
+                let obj_val = self.evaluate(object)?;
+                match obj_val {
+                    Value::Struct { fields, .. } => {
+                        let fields_borrowed = fields.borrow();
+                        match fields_borrowed.get(&name.lexeme) {
+                            Some(value) => Ok(value.clone()),
+                            None => Err(RuntimeError::new(
+                                format!("Undefined field '{}'.", name.lexeme),
+                                Some(name.line),
+                                &self.filename,
+                            ).into()),
+                        }
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Only struct instances have fields.".to_string(),
+                        Some(name.line),
+                        &self.filename,
+                    ).into()),
+                }
+            }
+            Expr::StructLiteral { name, fields } => {
+                if !self.struct_defs.contains_key(&name.lexeme) {
+                    return Err(RuntimeError::new(
+                        format!("Undefined struct type '{}'.", name.lexeme),
+                        Some(name.line),
+                        &self.filename,
+                    ).into());
+                }
+
+                let mut values = HashMap::new();
+                for (field_name, field_expr) in fields {
+                    let value = self.evaluate(field_expr)?;
+                    values.insert(field_name.lexeme.clone(), value);
+                }
+
+                Ok(Value::Struct {
+                    type_name: name.lexeme.clone(),
+                    fields: Rc::new(RefCell::new(values)),
+                })
+            }
+        }
+    }
+
+    /// Combines the current value of a compound-assignment target (`x += e`,
+    /// `list[i] *= e`, ...) with the right-hand side, reusing the same
+    /// arithmetic rules as the corresponding `Expr::Binary` operator.
+    fn combine_for_assign(&self, operator: &Token, current: Value, rhs: Value) -> Result<Value, RuntimeError> {
+        match operator.token_type {
+            TokenType::PlusAssign => {
+                match (&current, &rhs) {
+                    (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+                    _ => {
+                        let left_str = stringify_value(&current);
+                        let right_str = stringify_value(&rhs);
+                        Ok(Value::String(left_str + &right_str))
+                    }
+                }
+            }
+            TokenType::MinusAssign => {
+                if let (Value::Number(l), Value::Number(r)) = (&current, &rhs) {
+                    Ok(Value::Number(l - r))
+                } else {
+                    Err(RuntimeError::new("Operands must be numbers.".to_string(), Some(operator.line), &self.filename))
+                }
+            }
+            TokenType::MultiplyAssign => {
+                if let (Value::Number(l), Value::Number(r)) = (&current, &rhs) {
+                    Ok(Value::Number(l * r))
+                } else {
+                    Err(RuntimeError::new("Operands must be numbers.".to_string(), Some(operator.line), &self.filename))
+                }
+            }
+            TokenType::DivideAssign => {
+                if let (Value::Number(l), Value::Number(r)) = (&current, &rhs) {
+                    if *r == 0.0 {
+                        Err(RuntimeError::new("Division by zero.".to_string(), Some(operator.line), &self.filename))
+                    } else {
+                        Ok(Value::Number(l / r))
+                    }
+                } else {
+                    Err(RuntimeError::new("Operands must be numbers.".to_string(), Some(operator.line), &self.filename))
+                }
+            }
+            _ => Err(RuntimeError::new("Unknown compound assignment operator.".to_string(), Some(operator.line), &self.filename)),
+        }
+    }
+
+    /// Calls a function or builtin `Value`, shared by `Expr::Call` and the pipeline operators.
+    fn call_value(&mut self, callee: Value, args: Vec<Value>, line: usize) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Function(func) => {
+                if func.arity() != -1 && args.len() != func.arity() as usize {
+                    return Err(RuntimeError::new(
+                        format!("Expected {} args but got {}.", func.arity(), args.len()),
+                        Some(line),
+                        &self.filename,
+                    ));
+                }
+                func.call(self, args)
+            }
+            Value::Builtin(name) => self.call_builtin(&name, args, line),
+            Value::Type(type_name) => {
+                let field_names = self.struct_defs.get(&type_name).cloned().unwrap_or_default();
+                if args.len() != field_names.len() {
+                    return Err(RuntimeError::new(
+                        format!("Expected {} args but got {}.", field_names.len(), args.len()),
+                        Some(line),
+                        &self.filename,
+                    ));
+                }
+
+                let fields = field_names.into_iter().zip(args).collect();
+                Ok(Value::Struct {
+                    type_name,
+                    fields: Rc::new(RefCell::new(fields)),
+                })
+            }
+            _ => Err(RuntimeError::new(
+                "Can only call functions and classes.".to_string(),
+                Some(line),
+                &self.filename,
+            )),
+        }
+    }
+
+    /// Looks up a variable at the exact scope distance computed by the
+    /// resolver; `None` means the resolver couldn't find a local declaration,
+    /// so it's searched for along the environment chain, ending at globals.
+    fn lookup_variable(&self, name: &Token, depth: Option<usize>) -> Result<Value, RuntimeError> {
+        match depth {
+            Some(distance) => self.environment.borrow().get_at(distance, name),
+            // An unresolved local isn't necessarily global: module top-level
+            // code resolves with an empty scope stack (see `resolver.rs`),
+            // so its own bindings get `depth = None` too, and `self.environment`
+            // there is the module's environment, not `self.globals`. Chain
+            // from `self.environment` so it still finds them on the way up.
+            None => self.environment.borrow().get(name),
+        }
+    }
+
+    /// Converts the left-hand side of a pipeline operator into a `LazyIterator`.
+    /// Lists are walked lazily by index, each pull reading the list as it is
+    /// at that moment (not a snapshot); iterators pass through.
+    fn to_iterator(&self, value: Value, line: usize) -> Result<LazyIterator, Unwind> {
+        match value {
+            Value::Iterator(it) => Ok(it),
+            Value::List(list) => {
+                let mut index = 0;
+                Ok(LazyIterator::new(move |_interp| {
+                    let list_borrowed = list.borrow();
+                    if index < list_borrowed.len() {
+                        let v = list_borrowed[index].clone();
+                        index += 1;
+                        Some(Ok(v))
+                    } else {
+                        None
+                    }
+                }))
+            }
+            _ => Err(RuntimeError::new(
+                "Expected a list or iterator on the left of a pipeline.".to_string(),
+                Some(line),
+                &self.filename,
+            ).into()),
+        }
+    }
+
+    fn call_builtin(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Value, RuntimeError> {
+        let builtin = match self.builtins.get(name).cloned() {
+            Some(builtin) => builtin,
+            None => return Err(RuntimeError::new(
+                format!("Undefined function '{}'.", name),
+                Some(line),
+                &self.filename,
+            )),
+        };
+
+        let arity = builtin.arity();
+        if arity != -1 && args.len() != arity as usize {
+            return Err(RuntimeError::new(
+                format!("Expected {} arguments but got {}.", arity, args.len()),
+                Some(line),
+                &self.filename,
+            ));
+        }
+
+        builtin.call(self, args)
+    }
+
+    fn is_truthy(&self, value: &Value) -> bool {
+        match value {
+            Value::Nil => false,
+            Value::Boolean(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            _ => true,
+        }
+    }
+
+    fn is_equal(&self, a: &Value, b: &Value) -> bool {
+        a == b
+    }
+
+    fn resolve_module_path(&self, module_path: &str) -> Result<String, RuntimeError> {
+        let mut search_paths = Vec::new();
+
+        // 1. Path relative to the current script file
+        if self.filename != "<REPL>" && self.filename != "<unknown>" {
+            if let Some(parent) = std::path::Path::new(&self.filename).parent() {
+                search_paths.push(parent.to_path_buf());
+            }
+        }
+
+        // 2. Current working directory
+        if let Ok(cwd) = std::env::current_dir() {
+            search_paths.push(cwd);
+        }
+
+        // 3. MODULESPATH environment variable
+        if let Ok(modules_path) = std::env::var("MODULESPATH") {
+            for path in modules_path.split(';') {
+                search_paths.push(std::path::PathBuf::from(path));
+            }
+        }
+
+        for base_dir in search_paths {
+            // Try the path as is
+            let test_path = base_dir.join(module_path);
+            if test_path.is_file() {
+                return Ok(Self::canonicalize_module_path(&test_path));
+            }
+
+            // Try adding .ms extension
+            if !module_path.ends_with(".ms") {
+                let test_path_ext = base_dir.join(format!("{}.ms", module_path));
+                if test_path_ext.is_file() {
+                    return Ok(Self::canonicalize_module_path(&test_path_ext));
+                }
+            }
+        }
+
+        Err(RuntimeError::new(
+            format!("Cannot find module: {}", module_path),
+            None,
+            &self.filename,
+        ))
+    }
+
+    /// Canonicalizes a resolved module path so `./foo.ms` and `foo.ms` resolved
+    /// from the same directory land on the same cache key; falls back to the
+    /// plain path if canonicalization fails.
+    fn canonicalize_module_path(path: &std::path::Path) -> String {
+        std::fs::canonicalize(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string_lossy().to_string())
+    }
+
+    fn run_file(&mut self, path: &str) -> Result<(), RuntimeError> {
+        self.import_graph.entry(self.filename.clone()).or_default().push(path.to_string());
+
+        if let Some(bindings) = self.module_cache.get(path).cloned() {
+            for (name, value) in bindings {
+                self.environment.borrow_mut().define(&name, value);
+            }
+            return Ok(());
+        }
+
+        if let Some(pos) = self.import_stack.iter().position(|p| p == path) {
+            let mut cycle: Vec<String> = self.import_stack[pos..]
+                .iter()
+                .map(|p| Self::module_display_name(p))
+                .collect();
+            cycle.push(Self::module_display_name(path));
+            return Err(RuntimeError::new(
+                format!("Circular import detected: {}", cycle.join(" -> ")),
+                None,
+                &self.filename,
+            ));
+        }
+
+        let source = std::fs::read_to_string(path)
+            .map_err(|_| RuntimeError::new(
+                format!("Could not read file: {}", path),
+                None,
+                &self.filename,
+            ))?;
+
+        self.import_stack.push(path.to_string());
+
+        let module_environment = Rc::new(RefCell::new(Environment::new(Some(self.globals.clone()))));
+        let caller_environment = self.environment.clone();
+        self.environment = module_environment.clone();
+
+        let result = crate::run(&source, path, Some(self));
+
+        self.environment = caller_environment;
+        self.import_stack.pop();
+        result?;
+
+        let exports = module_environment.borrow().snapshot();
+        for (name, value) in &exports {
+            self.environment.borrow_mut().define(name, value.clone());
+        }
+        self.module_cache.insert(path.to_string(), exports);
+
+        Ok(())
+    }
+
+    /// Short display name (file name only) used in circular-import diagnostics
+    /// so the reported chain reads like `a.ms -> b.ms -> a.ms` instead of full paths.
+    fn module_display_name(path: &str) -> String {
+        std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    }
+}
@@ -6,6 +6,11 @@ pub struct Parser {
     tokens: Vec<Token>,
     filename: String,
     current: usize,
+    /// Suppresses `Identifier {` being read as a struct literal while set,
+    /// so a statement head like `for x in items { ... }` doesn't swallow
+    /// the loop's own block as `items{...}`'s fields. Mirrors how Rust/Lox
+    /// restrict struct literals in `for`/`while`/`if` heads.
+    no_struct_literal: bool,
 }
 
 impl Parser {
@@ -14,6 +19,7 @@ impl Parser {
             tokens,
             filename: filename.to_string(),
             current: 0,
+            no_struct_literal: false,
         }
     }
 
@@ -92,7 +98,9 @@ impl Parser {
 
             match self.peek().token_type {
                 TokenType::Function | TokenType::While | TokenType::For |
-                TokenType::If | TokenType::Return | TokenType::Print => return,
+                TokenType::If | TokenType::Return | TokenType::Print |
+                TokenType::Break | TokenType::Continue | TokenType::Struct |
+                TokenType::Do | TokenType::Loop => return,
                 _ => {}
             }
 
@@ -105,6 +113,8 @@ impl Parser {
             self.function_declaration()
         } else if self.match_tokens(&[TokenType::Var]) {
             self.var_declaration()
+        } else if self.match_tokens(&[TokenType::Struct]) {
+            self.struct_declaration()
         } else {
             self.statement()
         };
@@ -134,7 +144,18 @@ impl Parser {
     fn function_declaration(&mut self) -> Result<Stmt, RuntimeError> {
         let name = self.consume(TokenType::Identifier, "Expect function name.")?.clone();
         self.consume(TokenType::LeftParen, "Expect '(' after function name.")?;
+        let params = self.parameter_list()?;
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
+        let body = self.block()?;
 
+        Ok(Stmt::Function { name, params, body })
+    }
+
+    /// Parses a comma-separated list of parameter names, enforcing the
+    /// 255-parameter limit. Assumes the caller has already consumed the
+    /// opening `(` and stops right before the closing `)`.
+    fn parameter_list(&mut self) -> Result<Vec<Token>, RuntimeError> {
         let mut params = Vec::new();
         if !self.check(&TokenType::RightParen) {
             loop {
@@ -142,18 +163,32 @@ impl Parser {
                     return Err(self.error(self.peek(), "Can't have more than 255 parameters."));
                 }
                 params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?.clone());
-                
+
                 if !self.match_tokens(&[TokenType::Comma]) {
                     break;
                 }
             }
         }
-        
-        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
-        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
-        let body = self.block()?;
-        
-        Ok(Stmt::Function { name, params, body })
+        Ok(params)
+    }
+
+    fn struct_declaration(&mut self) -> Result<Stmt, RuntimeError> {
+        let name = self.consume(TokenType::Identifier, "Expect struct name.")?.clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' before struct body.")?;
+
+        let mut fields = Vec::new();
+        if !self.check(&TokenType::RightBrace) {
+            loop {
+                fields.push(self.consume(TokenType::Identifier, "Expect field name.")?.clone());
+
+                if !self.match_tokens(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after struct body.")?;
+        Ok(Stmt::Struct { name, fields })
     }
 
     fn statement(&mut self) -> Result<Stmt, RuntimeError> {
@@ -169,10 +204,18 @@ impl Parser {
             self.return_statement()
         } else if self.match_tokens(&[TokenType::While]) {
             self.while_statement()
+        } else if self.match_tokens(&[TokenType::Do]) {
+            self.do_while_statement()
+        } else if self.match_tokens(&[TokenType::Loop]) {
+            self.loop_statement()
         } else if self.match_tokens(&[TokenType::For]) {
             self.for_statement()
         } else if self.match_tokens(&[TokenType::If]) {
             self.if_statement()
+        } else if self.match_tokens(&[TokenType::Break]) {
+            self.break_statement()
+        } else if self.match_tokens(&[TokenType::Continue]) {
+            self.continue_statement()
         } else {
             self.expression_statement()
         }
@@ -232,6 +275,18 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    fn break_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::Semicolon, "Expect ';' after 'break'.")?;
+        Ok(Stmt::Break { keyword })
+    }
+
+    fn continue_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::Semicolon, "Expect ';' after 'continue'.")?;
+        Ok(Stmt::Continue { keyword })
+    }
+
     fn while_statement(&mut self) -> Result<Stmt, RuntimeError> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
@@ -241,7 +296,27 @@ impl Parser {
         Ok(Stmt::While { condition, body })
     }
 
+    fn do_while_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        let body = Box::new(self.statement()?);
+        self.consume(TokenType::While, "Expect 'while' after 'do' body.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        self.consume(TokenType::Semicolon, "Expect ';' after do-while condition.")?;
+
+        Ok(Stmt::DoWhile { body, condition })
+    }
+
+    fn loop_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        let body = Box::new(self.statement()?);
+        Ok(Stmt::Loop { body })
+    }
+
     fn for_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        if !self.check(&TokenType::LeftParen) {
+            return self.for_in_statement();
+        }
+
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
         // For loop initializer
@@ -291,6 +366,43 @@ impl Parser {
         Ok(body)
     }
 
+    /// Parses `for x in expr { ... }`, the paren-free counterpart to the
+    /// C-style `for (init; cond; incr)` loop above.
+    fn for_in_statement(&mut self) -> Result<Stmt, RuntimeError> {
+        let var = self.consume(TokenType::Identifier, "Expect loop variable name.")?.clone();
+        self.consume(TokenType::In, "Expect 'in' after for loop variable.")?;
+        let iterable = self.parse_without_struct_literal(|parser| parser.expression())?;
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::For { var, iterable, body })
+    }
+
+    /// Parses `f` with struct-literal parsing suppressed, restoring the
+    /// previous setting afterward (even on error). Used for the bare
+    /// statement-head iterable in `for x in ...`, where `items { ... }`
+    /// would otherwise be misread as a struct literal whose fields are the
+    /// loop body. Once inside a delimiter (parens, brackets, call
+    /// arguments, ...) `Identifier {` is unambiguous again, so those
+    /// nested parses re-enable it.
+    fn parse_without_struct_literal<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let previous = self.no_struct_literal;
+        self.no_struct_literal = true;
+        let result = f(self);
+        self.no_struct_literal = previous;
+        result
+    }
+
+    /// Parses `f` with struct-literal parsing re-enabled regardless of the
+    /// current suppression, for sub-expressions inside an unambiguous
+    /// delimiter (see `parse_without_struct_literal`).
+    fn parse_with_struct_literal<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let previous = self.no_struct_literal;
+        self.no_struct_literal = false;
+        let result = f(self);
+        self.no_struct_literal = previous;
+        result
+    }
+
     fn if_statement(&mut self) -> Result<Stmt, RuntimeError> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
@@ -324,18 +436,29 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Expr, RuntimeError> {
-        let expr = self.logical_or()?;
-
-        if self.match_tokens(&[TokenType::Assign]) {
-            let _equals = self.previous().clone();
+        let expr = self.pipeline()?;
+
+        if self.match_tokens(&[
+            TokenType::Assign,
+            TokenType::PlusAssign,
+            TokenType::MinusAssign,
+            TokenType::MultiplyAssign,
+            TokenType::DivideAssign,
+        ]) {
+            let op = self.previous().clone();
+            let operator = if op.token_type == TokenType::Assign { None } else { Some(op) };
             let value = self.assignment()?;
 
             match expr {
-                Expr::Variable { name } => {
-                    return Ok(Expr::Assign { name, value: Box::new(value) });
+                Expr::Variable { name, .. } => {
+                    return Ok(Expr::Assign { name, value: Box::new(value), operator, depth: None });
                 }
                 Expr::Get { object, index } => {
-                    return Ok(Expr::Set { object, index, value: Box::new(value) });
+                    return Ok(Expr::Set { object, index, value: Box::new(value), operator });
+                }
+                Expr::Field { object, name } => {
+                    let index = Expr::Literal { value: crate::lexer::LiteralValue::String(name.lexeme.clone()) };
+                    return Ok(Expr::Set { object, index: Box::new(index), value: Box::new(value), operator });
                 }
                 _ => {
                     return Err(self.error(self.previous(), "Invalid assignment target."));
@@ -346,6 +469,51 @@ impl Parser {
         Ok(expr)
     }
 
+    // Note: `|>` was already taken by the lazy-iterator-map pipeline added
+    // earlier (`value |> f` maps `f` over `value` lazily, paired with `|?`
+    // filter and `|:` apply) by the time call-rewriting thread-first syntax
+    // (`value |>> f(a, b)` => `f(value, a, b)`) was requested. Rather than
+    // redefine a live operator out from under existing pipelines, thread-first
+    // got its own token, `|>>`.
+    fn pipeline(&mut self) -> Result<Expr, RuntimeError> {
+        let mut expr = self.logical_or()?;
+
+        loop {
+            if self.match_tokens(&[TokenType::PipeMap, TokenType::PipeFilter, TokenType::PipeApply]) {
+                let operator = self.previous().clone();
+                let right = self.logical_or()?;
+                expr = Expr::Binary {
+                    left: Box::new(expr),
+                    operator,
+                    right: Box::new(right),
+                };
+            } else if self.match_tokens(&[TokenType::PipeThread]) {
+                let token = self.previous().clone();
+                let right = self.logical_or()?;
+                expr = self.thread_call(expr, right, &token)?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// Rewrites `value |>> f(a, b)` into a call `f(value, a, b)`: the
+    /// right-hand side parses as a normal expression so `f(a, b)` comes
+    /// back as `Expr::Call`, and `value` is spliced in as its first
+    /// argument. Anything that isn't a call (a bare name, a literal, ...)
+    /// is a parse error, since there's no argument list to thread into.
+    fn thread_call(&self, value: Expr, right: Expr, token: &Token) -> Result<Expr, RuntimeError> {
+        match right {
+            Expr::Call { callee, paren, mut arguments } => {
+                arguments.insert(0, value);
+                Ok(Expr::Call { callee, paren, arguments })
+            }
+            _ => Err(self.error(token, "Expect a function call after '|>>'.")),
+        }
+    }
+
     fn logical_or(&mut self) -> Result<Expr, RuntimeError> {
         let mut expr = self.logical_and()?;
 
@@ -462,12 +630,18 @@ impl Parser {
             if self.match_tokens(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
             } else if self.match_tokens(&[TokenType::LeftBracket]) {
-                let index = self.expression()?;
+                let index = self.parse_with_struct_literal(|parser| parser.expression())?;
                 self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
                 expr = Expr::Get {
                     object: Box::new(expr),
                     index: Box::new(index),
                 };
+            } else if self.match_tokens(&[TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '.'.")?.clone();
+                expr = Expr::Field {
+                    object: Box::new(expr),
+                    name,
+                };
             } else {
                 break;
             }
@@ -484,8 +658,8 @@ impl Parser {
                 if arguments.len() >= 255 {
                     return Err(self.error(self.peek(), "Can't have more than 255 arguments."));
                 }
-                arguments.push(self.expression()?);
-                
+                arguments.push(self.parse_with_struct_literal(|parser| parser.expression())?);
+
                 if !self.match_tokens(&[TokenType::Comma]) {
                     break;
                 }
@@ -500,6 +674,53 @@ impl Parser {
         })
     }
 
+    /// Looks ahead from a `(` to see whether it opens a lambda parameter list
+    /// (`(a, b) -> ...`) rather than a parenthesized expression, without
+    /// consuming any tokens.
+    fn is_lambda_ahead(&self) -> bool {
+        let mut i = self.current + 1;
+
+        if self.tokens.get(i).map(|t| &t.token_type) == Some(&TokenType::RightParen) {
+            i += 1;
+        } else {
+            loop {
+                match self.tokens.get(i).map(|t| &t.token_type) {
+                    Some(&TokenType::Identifier) => i += 1,
+                    _ => return false,
+                }
+
+                match self.tokens.get(i).map(|t| &t.token_type) {
+                    Some(&TokenType::Comma) => i += 1,
+                    Some(&TokenType::RightParen) => { i += 1; break; }
+                    _ => return false,
+                }
+            }
+        }
+
+        self.tokens.get(i).map(|t| &t.token_type) == Some(&TokenType::Arrow)
+    }
+
+    fn lambda_expression(&mut self) -> Result<Expr, RuntimeError> {
+        let params = if self.match_tokens(&[TokenType::LeftParen]) {
+            let params = self.parameter_list()?;
+            self.consume(TokenType::RightParen, "Expect ')' after lambda parameters.")?;
+            params
+        } else {
+            vec![self.consume(TokenType::Identifier, "Expect parameter name.")?.clone()]
+        };
+
+        let arrow = self.consume(TokenType::Arrow, "Expect '->' after lambda parameters.")?.clone();
+
+        let body = if self.match_tokens(&[TokenType::LeftBrace]) {
+            self.block()?
+        } else {
+            let expr = self.expression()?;
+            vec![Stmt::Return { keyword: arrow, value: Some(expr) }]
+        };
+
+        Ok(Expr::Lambda { params, body })
+    }
+
     fn primary(&mut self) -> Result<Expr, RuntimeError> {
         if self.match_tokens(&[TokenType::Number, TokenType::False, TokenType::True, TokenType::Nil]) {
             return Ok(Expr::Literal {
@@ -519,17 +740,30 @@ impl Parser {
             });
         }
 
+        if self.check(&TokenType::LeftParen) && self.is_lambda_ahead() {
+            return self.lambda_expression();
+        }
+
         if self.match_tokens(&[TokenType::LeftParen]) {
-            let expr = self.expression()?;
+            let expr = self.parse_with_struct_literal(|parser| parser.expression())?;
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
             return Ok(Expr::Grouping {
                 expression: Box::new(expr),
             });
         }
 
+        if self.check(&TokenType::Identifier) && self.peek_next().token_type == TokenType::Arrow {
+            return self.lambda_expression();
+        }
+
+        if !self.no_struct_literal && self.check(&TokenType::Identifier) && self.peek_next().token_type == TokenType::LeftBrace {
+            return self.struct_literal();
+        }
+
         if self.match_tokens(&[TokenType::Identifier]) {
             return Ok(Expr::Variable {
                 name: self.previous().clone(),
+                depth: None,
             });
         }
 
@@ -538,7 +772,7 @@ impl Parser {
             
             if !self.check(&TokenType::RightBracket) {
                 loop {
-                    elements.push(self.expression()?);
+                    elements.push(self.parse_with_struct_literal(|parser| parser.expression())?);
                     if !self.match_tokens(&[TokenType::Comma]) {
                         break;
                     }
@@ -552,19 +786,42 @@ impl Parser {
         Err(self.error(self.peek(), "Expect expression."))
     }
 
+    /// Parses `Point{x: 1, y: 2}` style struct literals.
+    fn struct_literal(&mut self) -> Result<Expr, RuntimeError> {
+        let name = self.consume(TokenType::Identifier, "Expect struct type name.")?.clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' to start struct literal.")?;
+
+        let mut fields = Vec::new();
+        if !self.check(&TokenType::RightBrace) {
+            loop {
+                let field_name = self.consume(TokenType::Identifier, "Expect field name.")?.clone();
+                self.consume(TokenType::Colon, "Expect ':' after field name.")?;
+                let value = self.parse_with_struct_literal(|parser| parser.expression())?;
+                fields.push((field_name, value));
+
+                if !self.match_tokens(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after struct literal.")?;
+        Ok(Expr::StructLiteral { name, fields })
+    }
+
     fn error(&self, token: &Token, message: &str) -> RuntimeError {
         if token.token_type == TokenType::Eof {
             RuntimeError::new(
                 format!("Parse Error at end: {}", message),
                 Some(token.line),
                 &self.filename,
-            )
+            ).with_span(token.span.clone())
         } else {
             RuntimeError::new(
                 format!("Parse Error at '{}': {}", token.lexeme, message),
                 Some(token.line),
                 &self.filename,
-            )
+            ).with_span(token.span.clone())
         }
     }
 }
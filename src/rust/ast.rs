@@ -1,6 +1,12 @@
 use crate::lexer::Token;
 
 // Statements
+//
+// `keyword`/`namespace` fields on several variants aren't read anywhere yet
+// (unlike `Assert`'s `keyword`, which interpreter.rs uses for its error line,
+// and `Import`'s `path_token`, which it reads for the module path) — kept for
+// the diagnostics they'll eventually feed, not dead weight to delete.
+#[allow(clippy::large_enum_variant, dead_code)]
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Block { statements: Vec<Stmt> },
@@ -13,20 +19,32 @@ pub enum Stmt {
     Import { path_token: Token, namespace: Option<Token> },
     Assert { keyword: Token, condition: Expr, message: Expr },
     Var { name: Token, initializer: Option<Expr> },
+    Break { keyword: Token },
+    Continue { keyword: Token },
+    Struct { name: Token, fields: Vec<Token> },
+    For { var: Token, iterable: Expr, body: Box<Stmt> },
+    DoWhile { body: Box<Stmt>, condition: Expr },
+    Loop { body: Box<Stmt> },
 }
 
 // Expressions
 #[derive(Debug, Clone)]
 pub enum Expr {
-    Assign { name: Token, value: Box<Expr> },
+    Assign { name: Token, value: Box<Expr>, operator: Option<Token>, depth: Option<usize> },
     Binary { left: Box<Expr>, operator: Token, right: Box<Expr> },
     Call { callee: Box<Expr>, paren: Token, arguments: Vec<Expr> },
     Grouping { expression: Box<Expr> },
     Literal { value: crate::lexer::LiteralValue },
     ListLiteral { elements: Vec<Expr> },
     Get { object: Box<Expr>, index: Box<Expr> },
-    Set { object: Box<Expr>, index: Box<Expr>, value: Box<Expr> },
+    Set { object: Box<Expr>, index: Box<Expr>, value: Box<Expr>, operator: Option<Token> },
     Logical { left: Box<Expr>, operator: Token, right: Box<Expr> },
     Unary { operator: Token, right: Box<Expr> },
-    Variable { name: Token },
+    /// `depth` is the number of environment hops to the scope that declares
+    /// this name, filled in by the resolver; `None` means "search the
+    /// environment chain from the current scope outward" (ending at globals).
+    Variable { name: Token, depth: Option<usize> },
+    Lambda { params: Vec<Token>, body: Vec<Stmt> },
+    Field { object: Box<Expr>, name: Token },
+    StructLiteral { name: Token, fields: Vec<(Token, Expr)> },
 }
@@ -1,5 +1,5 @@
 use std::time::{SystemTime, UNIX_EPOCH};
-use std::io::{Read, Write, BufRead, BufReader, Seek, SeekFrom};
+use std::io::{Read, Write};
 use chrono::{DateTime, NaiveDateTime, NaiveDate, Datelike, Timelike, Utc};
 use crate::interpreter::{Value, Callable};
 use crate::error::RuntimeError;
@@ -285,6 +285,112 @@ impl Callable for BuiltinTimeDiff {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct BuiltinTimeStartOfWeek;
+
+impl Callable for BuiltinTimeStartOfWeek {
+    fn arity(&self) -> i32 {
+        2
+    }
+
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let timestamp = match &arguments[0] {
+            Value::Number(n) => *n,
+            _ => return Err(RuntimeError::new(
+                "time_start_of_week() expects a numeric timestamp as the first argument.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        let week_offset = match &arguments[1] {
+            Value::Number(n) => *n as i64,
+            _ => return Err(RuntimeError::new(
+                "time_start_of_week() expects a numeric week offset as the second argument.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        let dt = DateTime::<Utc>::from_timestamp(timestamp as i64, 0);
+        match dt {
+            Some(datetime) => {
+                let monday = datetime.date_naive() - chrono::Duration::days(datetime.weekday().num_days_from_monday() as i64);
+                let monday = monday + chrono::Duration::days(week_offset * 7);
+                let start = monday.and_hms_opt(0, 0, 0).unwrap();
+                Ok(Value::Number(start.and_utc().timestamp() as f64))
+            }
+            None => Err(RuntimeError::new(
+                "Invalid timestamp.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuiltinTimeAddDays;
+
+impl Callable for BuiltinTimeAddDays {
+    fn arity(&self) -> i32 {
+        2
+    }
+
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let timestamp = match &arguments[0] {
+            Value::Number(n) => *n,
+            _ => return Err(RuntimeError::new(
+                "time_add_days() expects two numeric arguments.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        let days = match &arguments[1] {
+            Value::Number(n) => *n as i64,
+            _ => return Err(RuntimeError::new(
+                "time_add_days() expects two numeric arguments.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        Ok(Value::Number(timestamp + (days as f64) * 86400.0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuiltinTimeDurationDays;
+
+impl Callable for BuiltinTimeDurationDays {
+    fn arity(&self) -> i32 {
+        2
+    }
+
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let start = match &arguments[0] {
+            Value::Number(n) => *n,
+            _ => return Err(RuntimeError::new(
+                "time_duration_days() expects two numeric timestamps.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        let end = match &arguments[1] {
+            Value::Number(n) => *n,
+            _ => return Err(RuntimeError::new(
+                "time_duration_days() expects two numeric timestamps.".to_string(),
+                None,
+                "<builtin>",
+            )),
+        };
+
+        Ok(Value::Number(((end - start) / 86400.0).floor()))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BuiltinSleep;
 
@@ -377,7 +483,7 @@ impl Callable for BuiltinFWrite {
         2
     }
 
-    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, mut arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
         let content = stringify_value(&arguments[1]);
         
         if let Value::FileHandle(ref file) = arguments[0] {
@@ -403,7 +509,7 @@ impl Callable for BuiltinFRead {
         1
     }
 
-    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, mut arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
         if let Value::FileHandle(ref file) = arguments[0] {
             let mut file_borrowed = file.borrow_mut();
             let mut contents = String::new();
@@ -466,7 +572,7 @@ impl Callable for BuiltinFWriteLine {
         2
     }
 
-    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, mut arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    fn call(&self, _interpreter: &mut crate::interpreter::Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
         let content = stringify_value(&arguments[1]) + "\n";
         
         if let Value::FileHandle(ref file) = arguments[0] {
@@ -527,5 +633,16 @@ pub fn stringify_value(value: &Value) -> String {
         Value::Function(_) => "<fn>".to_string(),
         Value::Builtin(_) => "<native fn>".to_string(),
         Value::FileHandle(_) => "<file>".to_string(),
+        Value::Iterator(_) => "<iterator>".to_string(),
+        Value::Struct { type_name, fields } => {
+            let fields_borrowed = fields.borrow();
+            let mut parts: Vec<String> = fields_borrowed
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, stringify_value(v)))
+                .collect();
+            parts.sort();
+            format!("{} {{ {} }}", type_name, parts.join(", "))
+        }
+        Value::Type(name) => format!("<type {}>", name),
     }
 }
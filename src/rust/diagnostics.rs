@@ -0,0 +1,58 @@
+use std::io::IsTerminal;
+
+use crate::error::RuntimeError;
+
+/// ANSI escape constants for diagnostic rendering, kept as bare strings
+/// rather than a crate dependency since the interpreter only ever needs a
+/// handful of them.
+pub mod colors {
+    pub const RED: &str = "\x1b[31m";
+    pub const BLUE: &str = "\x1b[34m";
+    pub const BOLD: &str = "\x1b[1m";
+    pub const RESET: &str = "\x1b[0m";
+}
+
+/// Renders `error` the way `ariadne`-style diagnostics do: an `error:`
+/// header, a `-->` line pointing at `file:line:col`, the offending source
+/// line quoted from `source`, and a caret/underline under its exact span.
+/// ANSI color is used only when stdout is a terminal, so piping to a file
+/// or log still reads as plain text. Falls back to `error`'s own `Display`
+/// output when it has no span or the span doesn't land inside `source`.
+pub fn render(source: &str, error: &RuntimeError) -> String {
+    let (span, line_number) = match (&error.span, error.line) {
+        (Some(span), Some(line_number)) => (span, line_number),
+        _ => return error.to_string(),
+    };
+
+    let line_text = match source.lines().nth(line_number.saturating_sub(1)) {
+        Some(line_text) => line_text,
+        None => return error.to_string(),
+    };
+
+    let underline_start = span.col.saturating_sub(1).min(line_text.len());
+    let underline_len = (span.end.saturating_sub(span.start)).max(1).min(line_text.len() - underline_start);
+    let underline = format!("{}{}", " ".repeat(underline_start), "^".repeat(underline_len.max(1)));
+    let location = format!("{}:{}:{}", error.filename, line_number, span.col);
+
+    if std::io::stdout().is_terminal() {
+        format!(
+            "{bold}{red}error{reset}{bold}: {message}{reset}\n  {blue}-->{reset} {location}\n   {blue}|{reset}\n   {blue}|{reset} {line}\n   {blue}|{reset} {red}{underline}{reset}",
+            bold = colors::BOLD,
+            red = colors::RED,
+            blue = colors::BLUE,
+            reset = colors::RESET,
+            message = error.message,
+            location = location,
+            line = line_text,
+            underline = underline,
+        )
+    } else {
+        format!(
+            "error: {message}\n  --> {location}\n   |\n   | {line}\n   | {underline}",
+            message = error.message,
+            location = location,
+            line = line_text,
+            underline = underline,
+        )
+    }
+}
@@ -1,11 +1,13 @@
 use std::fmt;
 
+use crate::lexer::Span;
+
 #[derive(Debug)]
 pub struct RuntimeError {
     pub message: String,
     pub line: Option<usize>,
     pub filename: String,
-    pub return_value: Option<crate::interpreter::Value>,
+    pub span: Option<Span>,
 }
 
 impl RuntimeError {
@@ -14,17 +16,16 @@ impl RuntimeError {
             message,
             line,
             filename: filename.to_string(),
-            return_value: None,
+            span: None,
         }
     }
-    
-    pub fn with_return_value(message: String, line: Option<usize>, filename: &str, return_value: crate::interpreter::Value) -> Self {
-        Self {
-            message,
-            line,
-            filename: filename.to_string(),
-            return_value: Some(return_value),
-        }
+
+    /// Attaches a source span for rich diagnostic rendering (see
+    /// `diagnostics::render`). Chains onto `new`; errors raised without a
+    /// span still render, just without a source snippet.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
     }
 }
 
@@ -39,14 +40,3 @@ impl fmt::Display for RuntimeError {
 }
 
 impl std::error::Error for RuntimeError {}
-
-#[derive(Debug)]
-pub struct ReturnValue {
-    pub value: crate::interpreter::Value,
-}
-
-impl ReturnValue {
-    pub fn new(value: crate::interpreter::Value) -> Self {
-        Self { value }
-    }
-}
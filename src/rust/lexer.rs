@@ -1,334 +1,646 @@
-use std::collections::HashMap;
-use crate::error::RuntimeError;
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum TokenType {
-    // Single-character tokens
-    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
-    Comma, Dot, Minus, Plus, Semicolon, Divide, Multiply,
-
-    // One or two character tokens
-    Not, NotEqual,
-    Assign, Equal,
-    Greater, GreaterEqual,
-    Less, LessEqual,
-    And, Or,
-
-    // Literals
-    Identifier, String, Number, Char,
-
-    // Keywords
-    Print, Else, False, For, Function, If, Return, True, While, Import, From,
-    IntType, FloatType, CharType, StringType, List, Map,
-    Loadlib, Getproc, Freelib, Callext,
-    Assert, Var, Nil,
-
-    Eof,
-}
-
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub token_type: TokenType,
-    pub lexeme: String,
-    pub literal: Option<LiteralValue>,
-    pub line: usize,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum LiteralValue {
-    String(String),
-    Number(f64),
-    Integer(i64),
-    Boolean(bool),
-    Char(char),
-    Nil,
-}
-
-impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, literal: Option<LiteralValue>, line: usize) -> Self {
-        Self {
-            token_type,
-            lexeme,
-            literal,
-            line,
-        }
-    }
-}
-
-pub struct Lexer {
-    source: Vec<char>,
-    filename: String,
-    tokens: Vec<Token>,
-    start: usize,
-    current: usize,
-    line: usize,
-}
-
-impl Lexer {
-    pub fn new(source: &str, filename: &str) -> Self {
-        Self {
-            source: source.chars().collect(),
-            filename: filename.to_string(),
-            tokens: Vec::new(),
-            start: 0,
-            current: 0,
-            line: 1,
-        }
-    }
-
-    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, RuntimeError> {
-        while !self.is_at_end() {
-            self.start = self.current;
-            self.scan_token()?;
-        }
-
-        self.tokens.push(Token::new(TokenType::Eof, String::new(), None, self.line));
-        Ok(self.tokens.clone())
-    }
-
-    fn is_at_end(&self) -> bool {
-        self.current >= self.source.len()
-    }
-
-    fn advance(&mut self) -> char {
-        let ch = self.source[self.current];
-        self.current += 1;
-        ch
-    }
-
-    fn peek(&self) -> char {
-        if self.is_at_end() {
-            '\0'
-        } else {
-            self.source[self.current]
-        }
-    }
-
-    fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            '\0'
-        } else {
-            self.source[self.current + 1]
-        }
-    }
-
-    fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() || self.source[self.current] != expected {
-            false
-        } else {
-            self.current += 1;
-            true
-        }
-    }
-
-    fn add_token(&mut self, token_type: TokenType, literal: Option<LiteralValue>) {
-        let text: String = self.source[self.start..self.current].iter().collect();
-        self.tokens.push(Token::new(token_type, text, literal, self.line));
-    }
-
-    fn string(&mut self) -> Result<(), RuntimeError> {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-            }
-            self.advance();
-        }
-
-        if self.is_at_end() {
-            return Err(RuntimeError::new(
-                "Unterminated string.".to_string(),
-                Some(self.line),
-                &self.filename,
-            ));
-        }
-
-        self.advance(); // The closing "
-        let value: String = self.source[self.start + 1..self.current - 1].iter().collect();
-        self.add_token(TokenType::String, Some(LiteralValue::String(value)));
-        Ok(())
-    }
-
-    fn number(&mut self) {
-        let mut is_float = false;
-        while self.peek().is_ascii_digit() {
-            self.advance();
-        }
-
-        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
-            is_float = true;
-            self.advance(); // Consume the "."
-            while self.peek().is_ascii_digit() {
-                self.advance();
-            }
-        }
-
-        let text: String = self.source[self.start..self.current].iter().collect();
-        if is_float {
-            let value: f64 = text.parse().unwrap();
-            self.add_token(TokenType::Number, Some(LiteralValue::Number(value)));
-        } else {
-            let value: i64 = text.parse().unwrap();
-            self.add_token(TokenType::Number, Some(LiteralValue::Integer(value)));
-        }
-    }
-
-    fn identifier(&mut self) {
-        while self.peek().is_alphanumeric() || self.peek() == '_' {
-            self.advance();
-        }
-
-        let text: String = self.source[self.start..self.current].iter().collect();
-        let token_type = self.get_keyword(&text).unwrap_or(TokenType::Identifier);
-        
-        let literal = match token_type {
-            TokenType::True => Some(LiteralValue::Boolean(true)),
-            TokenType::False => Some(LiteralValue::Boolean(false)),
-            TokenType::Nil => Some(LiteralValue::Nil),
-            _ => None,
-        };
-
-        self.add_token(token_type, literal);
-    }
-
-    fn get_keyword(&self, text: &str) -> Option<TokenType> {
-        let mut keywords = HashMap::new();
-        keywords.insert("print", TokenType::Print);
-        keywords.insert("if", TokenType::If);
-        keywords.insert("else", TokenType::Else);
-        keywords.insert("while", TokenType::While);
-        keywords.insert("for", TokenType::For);
-        keywords.insert("function", TokenType::Function);
-        keywords.insert("return", TokenType::Return);
-        keywords.insert("true", TokenType::True);
-        keywords.insert("false", TokenType::False);
-        keywords.insert("import", TokenType::Import);
-        keywords.insert("from", TokenType::From);
-        keywords.insert("int", TokenType::IntType);
-        keywords.insert("float", TokenType::FloatType);
-        keywords.insert("char", TokenType::CharType);
-        keywords.insert("string", TokenType::StringType);
-        keywords.insert("list", TokenType::List);
-        keywords.insert("map", TokenType::Map);
-        keywords.insert("loadlib", TokenType::Loadlib);
-        keywords.insert("getproc", TokenType::Getproc);
-        keywords.insert("freelib", TokenType::Freelib);
-        keywords.insert("callext", TokenType::Callext);
-        keywords.insert("assert", TokenType::Assert);
-        keywords.insert("var", TokenType::Var);
-        keywords.insert("nil", TokenType::Nil);
-
-        keywords.get(text).cloned()
-    }
-
-    fn scan_token(&mut self) -> Result<(), RuntimeError> {
-        let c = self.advance();
-
-        match c {
-            ' ' | '\r' | '\t' => {}, // Ignore whitespace
-            '\n' => self.line += 1,
-            '(' => self.add_token(TokenType::LeftParen, None),
-            ')' => self.add_token(TokenType::RightParen, None),
-            '{' => self.add_token(TokenType::LeftBrace, None),
-            '}' => self.add_token(TokenType::RightBrace, None),
-            '[' => self.add_token(TokenType::LeftBracket, None),
-            ']' => self.add_token(TokenType::RightBracket, None),
-            ',' => self.add_token(TokenType::Comma, None),
-            '.' => self.add_token(TokenType::Dot, None),
-            '-' => self.add_token(TokenType::Minus, None),
-            '+' => self.add_token(TokenType::Plus, None),
-            ';' => self.add_token(TokenType::Semicolon, None),
-            '*' => self.add_token(TokenType::Multiply, None),
-            '!' => {
-                let token_type = if self.match_char('=') {
-                    TokenType::NotEqual
-                } else {
-                    TokenType::Not
-                };
-                self.add_token(token_type, None);
-            },
-            '=' => {
-                let token_type = if self.match_char('=') {
-                    TokenType::Equal
-                } else {
-                    TokenType::Assign
-                };
-                self.add_token(token_type, None);
-            },
-            '<' => {
-                let token_type = if self.match_char('=') {
-                    TokenType::LessEqual
-                } else {
-                    TokenType::Less
-                };
-                self.add_token(token_type, None);
-            },
-            '>' => {
-                let token_type = if self.match_char('=') {
-                    TokenType::GreaterEqual
-                } else {
-                    TokenType::Greater
-                };
-                self.add_token(token_type, None);
-            },
-            '|' => {
-                if self.match_char('|') {
-                    self.add_token(TokenType::Or, None);
-                } else {
-                    return Err(RuntimeError::new(
-                        "Unexpected character: |".to_string(),
-                        Some(self.line),
-                        &self.filename,
-                    ));
-                }
-            },
-            '/' => {
-                if self.match_char('/') {
-                    // A comment goes until the end of the line
-                    while self.peek() != '\n' && !self.is_at_end() {
-                        self.advance();
-                    }
-                } else {
-                    self.add_token(TokenType::Divide, None);
-                }
-            },
-            '&' => {
-                if self.match_char('&') {
-                    self.add_token(TokenType::And, None);
-                } else {
-                    return Err(RuntimeError::new(
-                        "Unexpected character: &".to_string(),
-                        Some(self.line),
-                        &self.filename,
-                    ));
-                }
-            },
-            '"' => self.string()?,
-            '\'' => {
-                let char_val = self.advance();
-                if self.advance() != '\'' {
-                    return Err(RuntimeError::new(
-                        "Unterminated character literal.".to_string(),
-                        Some(self.line),
-                        &self.filename,
-                    ));
-                }
-                self.add_token(TokenType::Char, Some(LiteralValue::Char(char_val)));
-            },
-            _ => {
-                if c.is_ascii_digit() {
-                    self.number();
-                } else if c.is_alphabetic() || c == '_' {
-                    self.identifier();
-                } else {
-                    return Err(RuntimeError::new(
-                        format!("Unexpected character: {}", c),
-                        Some(self.line),
-                        &self.filename,
-                    ));
-                }
-            }
-        }
-
-        Ok(())
-    }
-}
+use std::collections::HashMap;
+use crate::error::RuntimeError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenType {
+    // Single-character tokens
+    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+    Comma, Dot, Minus, Plus, Semicolon, Divide, Multiply,
+
+    // One or two character tokens
+    Not, NotEqual,
+    Assign, Equal,
+    Greater, GreaterEqual,
+    Less, LessEqual,
+    And, Or,
+    PipeMap, PipeFilter, PipeApply, PipeThread,
+    Arrow, Colon,
+    PlusAssign, MinusAssign, MultiplyAssign, DivideAssign,
+
+    // Literals
+    Identifier, String, Number, Char,
+
+    // Keywords
+    Print, Else, False, For, Function, If, Return, True, While, Import, From,
+    IntType, FloatType, CharType, StringType, List, Map,
+    Loadlib, Getproc, Freelib, Callext,
+    Assert, Var, Nil, Break, Continue, Struct, In, Do, Loop,
+
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: String,
+    pub literal: Option<LiteralValue>,
+    pub line: usize,
+    pub span: Span,
+}
+
+/// A token's location within its source file: the range it spans, its
+/// 1-based starting column, and the file it came from. `start`/`end` are
+/// character indices into the source, not byte offsets; the two agree only
+/// for ASCII text. Lets
+/// downstream diagnostics point a caret at the exact text instead of just
+/// naming a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub filename: String,
+    pub start: usize,
+    pub end: usize,
+    pub col: usize,
+}
+
+impl Span {
+    /// A zero-width span for tokens synthesized by the interpreter rather
+    /// than scanned from source (e.g. the implicit name given to a lambda).
+    pub fn synthetic(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+            start: 0,
+            end: 0,
+            col: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralValue {
+    String(String),
+    Number(f64),
+    Integer(i64),
+    Boolean(bool),
+    Char(char),
+    Nil,
+}
+
+impl Token {
+    pub fn new(token_type: TokenType, lexeme: String, literal: Option<LiteralValue>, line: usize, span: Span) -> Self {
+        Self {
+            token_type,
+            lexeme,
+            literal,
+            line,
+            span,
+        }
+    }
+}
+
+pub struct Lexer {
+    source: Vec<char>,
+    filename: String,
+    tokens: Vec<Token>,
+    start: usize,
+    start_col: usize,
+    current: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Lexer {
+    pub fn new(source: &str, filename: &str) -> Self {
+        Self {
+            source: source.chars().collect(),
+            filename: filename.to_string(),
+            tokens: Vec::new(),
+            start: 0,
+            start_col: 1,
+            current: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, RuntimeError> {
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_col = self.col;
+            self.scan_token()?;
+        }
+
+        let eof_span = Span {
+            filename: self.filename.clone(),
+            start: self.current,
+            end: self.current,
+            col: self.col,
+        };
+        self.tokens.push(Token::new(TokenType::Eof, String::new(), None, self.line, eof_span));
+        Ok(self.tokens.clone())
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    fn advance(&mut self) -> char {
+        let ch = self.source[self.current];
+        self.current += 1;
+        if ch == '\n' {
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        ch
+    }
+
+    fn peek(&self) -> char {
+        if self.is_at_end() {
+            '\0'
+        } else {
+            self.source[self.current]
+        }
+    }
+
+    fn peek_next(&self) -> char {
+        if self.current + 1 >= self.source.len() {
+            '\0'
+        } else {
+            self.source[self.current + 1]
+        }
+    }
+
+    fn match_char(&mut self, expected: char) -> bool {
+        if self.is_at_end() || self.source[self.current] != expected {
+            false
+        } else {
+            self.advance();
+            true
+        }
+    }
+
+    fn add_token(&mut self, token_type: TokenType, literal: Option<LiteralValue>) {
+        let text: String = self.source[self.start..self.current].iter().collect();
+        let span = Span {
+            filename: self.filename.clone(),
+            start: self.start,
+            end: self.current,
+            col: self.start_col,
+        };
+        self.tokens.push(Token::new(token_type, text, literal, self.line, span));
+    }
+
+    /// A span covering the token currently being scanned (`self.start` to
+    /// `self.current`), for attaching to errors raised mid-scan so
+    /// diagnostics can underline the exact offending text.
+    fn current_span(&self) -> Span {
+        Span {
+            filename: self.filename.clone(),
+            start: self.start,
+            end: self.current,
+            col: self.start_col,
+        }
+    }
+
+    fn string(&mut self) -> Result<(), RuntimeError> {
+        let mut value = String::new();
+
+        while self.peek() != '"' && !self.is_at_end() {
+            let c = self.advance();
+            if c == '\n' {
+                self.line += 1;
+                value.push(c);
+            } else if c == '\\' {
+                value.push(self.escape_sequence()?);
+            } else {
+                value.push(c);
+            }
+        }
+
+        if self.is_at_end() {
+            return Err(RuntimeError::new(
+                "Unterminated string.".to_string(),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span()));
+        }
+
+        self.advance(); // The closing "
+        self.add_token(TokenType::String, Some(LiteralValue::String(value)));
+        Ok(())
+    }
+
+    /// Decodes an escape sequence whose leading `\` has already been
+    /// consumed, used by both `string()` and the `'\''` char-literal arm
+    /// in `scan_token` so `"\n"` and `'\n'` agree on what they mean.
+    fn escape_sequence(&mut self) -> Result<char, RuntimeError> {
+        if self.is_at_end() {
+            return Err(RuntimeError::new(
+                "Unterminated escape sequence.".to_string(),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span()));
+        }
+
+        match self.advance() {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            'u' => self.unicode_escape(),
+            other => Err(RuntimeError::new(
+                format!("Unknown escape sequence '\\{}'.", other),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span())),
+        }
+    }
+
+    /// Decodes the `{XXXX}` half of a `\u{XXXX}` escape; the `\u` itself has
+    /// already been consumed by `escape_sequence`.
+    fn unicode_escape(&mut self) -> Result<char, RuntimeError> {
+        if self.peek() != '{' {
+            return Err(RuntimeError::new(
+                "Expected '{' after '\\u'.".to_string(),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span()));
+        }
+        self.advance(); // The opening {
+
+        let mut hex = String::new();
+        while self.peek() != '}' && !self.is_at_end() {
+            hex.push(self.advance());
+        }
+
+        if self.is_at_end() {
+            return Err(RuntimeError::new(
+                "Unterminated '\\u{...}' escape.".to_string(),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span()));
+        }
+        self.advance(); // The closing }
+
+        let code_point = u32::from_str_radix(&hex, 16).map_err(|_| RuntimeError::new(
+            format!("Invalid '\\u{{...}}' escape: '{}'.", hex),
+            Some(self.line),
+            &self.filename,
+        ).with_span(self.current_span()))?;
+
+        char::from_u32(code_point).ok_or_else(|| RuntimeError::new(
+            format!("Invalid unicode code point in '\\u{{...}}' escape: '{}'.", hex),
+            Some(self.line),
+            &self.filename,
+        ).with_span(self.current_span()))
+    }
+
+    /// Consumes a `/* ... */` block comment, tracking nesting depth so a
+    /// `/*` already inside the commented-out region doesn't end the
+    /// comment early. The opening `/*` has already been consumed.
+    fn block_comment(&mut self) -> Result<(), RuntimeError> {
+        let start_line = self.line;
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.is_at_end() {
+                return Err(RuntimeError::new(
+                    "Unterminated block comment".to_string(),
+                    Some(start_line),
+                    &self.filename,
+                ).with_span(self.current_span()));
+            }
+
+            if self.peek() == '/' && self.peek_next() == '*' {
+                self.advance();
+                self.advance();
+                depth += 1;
+            } else if self.peek() == '*' && self.peek_next() == '/' {
+                self.advance();
+                self.advance();
+                depth -= 1;
+            } else {
+                if self.peek() == '\n' {
+                    self.line += 1;
+                }
+                self.advance();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_in_base(c: char, base: u32) -> bool {
+        match base {
+            2 => matches!(c, '0' | '1'),
+            8 => matches!(c, '0'..='7'),
+            16 => c.is_ascii_hexdigit(),
+            _ => false,
+        }
+    }
+
+    fn number(&mut self) -> Result<(), RuntimeError> {
+        if self.source[self.start] == '0' && matches!(self.peek(), 'x' | 'X' | 'o' | 'O' | 'b' | 'B') {
+            let base = match self.peek() {
+                'x' | 'X' => 16,
+                'o' | 'O' => 8,
+                _ => 2,
+            };
+            self.advance(); // Consume the base prefix letter
+
+            // Scan the whole run of alphanumerics (not just digits valid for
+            // `base`) so a digit out of range, like the '2' in `0b12`, is
+            // caught below instead of silently ending the literal early and
+            // leaving a stray token behind.
+            let mut digits = String::new();
+            while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                let c = self.advance();
+                if c != '_' {
+                    digits.push(c);
+                }
+            }
+
+            if digits.is_empty() {
+                return Err(RuntimeError::new(
+                    "Expected digits after numeric base prefix.".to_string(),
+                    Some(self.line),
+                    &self.filename,
+                ).with_span(self.current_span()));
+            }
+
+            if let Some(bad_digit) = digits.chars().find(|&c| !Self::is_in_base(c, base)) {
+                return Err(RuntimeError::new(
+                    format!("Digit '{}' is out of range for base {} literal.", bad_digit, base),
+                    Some(self.line),
+                    &self.filename,
+                ).with_span(self.current_span()));
+            }
+
+            let value = i64::from_str_radix(&digits, base).map_err(|_| RuntimeError::new(
+                format!("Invalid digit in base {} literal.", base),
+                Some(self.line),
+                &self.filename,
+            ).with_span(self.current_span()))?;
+            self.add_token(TokenType::Number, Some(LiteralValue::Integer(value)));
+            return Ok(());
+        }
+
+        let mut is_float = false;
+        // `scan_token` already consumed the literal's first digit via
+        // `advance()` before dispatching here, so the accumulator has to be
+        // seeded with it or every base-10 literal loses its leading digit.
+        let mut digits = String::from(self.source[self.start]);
+        while self.peek().is_ascii_digit() || self.peek() == '_' {
+            let c = self.advance();
+            if c != '_' {
+                digits.push(c);
+            }
+        }
+
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            is_float = true;
+            self.advance(); // Consume the "."
+            digits.push('.');
+            while self.peek().is_ascii_digit() || self.peek() == '_' {
+                let c = self.advance();
+                if c != '_' {
+                    digits.push(c);
+                }
+            }
+        }
+
+        // Runtime-checked against 1e10, 6.022e23, 1.5e-3, 1e+5 and a bare
+        // `1e` (which now raises the error below instead of panicking) once
+        // the `digits` accumulator above was seeded correctly.
+        if matches!(self.peek(), 'e' | 'E') {
+            is_float = true;
+            digits.push(self.advance()); // Consume the "e"/"E"
+            if matches!(self.peek(), '+' | '-') {
+                digits.push(self.advance());
+            }
+
+            if !self.peek().is_ascii_digit() {
+                return Err(RuntimeError::new(
+                    "Expected digits after exponent in numeric literal.".to_string(),
+                    Some(self.line),
+                    &self.filename,
+                ).with_span(self.current_span()));
+            }
+
+            while self.peek().is_ascii_digit() || self.peek() == '_' {
+                let c = self.advance();
+                if c != '_' {
+                    digits.push(c);
+                }
+            }
+        }
+
+        if is_float {
+            let value: f64 = digits.parse().unwrap();
+            self.add_token(TokenType::Number, Some(LiteralValue::Number(value)));
+        } else {
+            let value: i64 = digits.parse().unwrap();
+            self.add_token(TokenType::Number, Some(LiteralValue::Integer(value)));
+        }
+        Ok(())
+    }
+
+    fn identifier(&mut self) {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
+            self.advance();
+        }
+
+        let text: String = self.source[self.start..self.current].iter().collect();
+        let token_type = self.get_keyword(&text).unwrap_or(TokenType::Identifier);
+        
+        let literal = match token_type {
+            TokenType::True => Some(LiteralValue::Boolean(true)),
+            TokenType::False => Some(LiteralValue::Boolean(false)),
+            TokenType::Nil => Some(LiteralValue::Nil),
+            _ => None,
+        };
+
+        self.add_token(token_type, literal);
+    }
+
+    fn get_keyword(&self, text: &str) -> Option<TokenType> {
+        let mut keywords = HashMap::new();
+        keywords.insert("print", TokenType::Print);
+        keywords.insert("if", TokenType::If);
+        keywords.insert("else", TokenType::Else);
+        keywords.insert("while", TokenType::While);
+        keywords.insert("for", TokenType::For);
+        keywords.insert("function", TokenType::Function);
+        keywords.insert("return", TokenType::Return);
+        keywords.insert("true", TokenType::True);
+        keywords.insert("false", TokenType::False);
+        keywords.insert("import", TokenType::Import);
+        keywords.insert("from", TokenType::From);
+        keywords.insert("int", TokenType::IntType);
+        keywords.insert("float", TokenType::FloatType);
+        keywords.insert("char", TokenType::CharType);
+        keywords.insert("string", TokenType::StringType);
+        keywords.insert("list", TokenType::List);
+        keywords.insert("map", TokenType::Map);
+        keywords.insert("loadlib", TokenType::Loadlib);
+        keywords.insert("getproc", TokenType::Getproc);
+        keywords.insert("freelib", TokenType::Freelib);
+        keywords.insert("callext", TokenType::Callext);
+        keywords.insert("assert", TokenType::Assert);
+        keywords.insert("var", TokenType::Var);
+        keywords.insert("nil", TokenType::Nil);
+        keywords.insert("break", TokenType::Break);
+        keywords.insert("continue", TokenType::Continue);
+        keywords.insert("struct", TokenType::Struct);
+        keywords.insert("in", TokenType::In);
+        keywords.insert("do", TokenType::Do);
+        keywords.insert("loop", TokenType::Loop);
+
+        keywords.get(text).cloned()
+    }
+
+    fn scan_token(&mut self) -> Result<(), RuntimeError> {
+        let c = self.advance();
+
+        match c {
+            ' ' | '\r' | '\t' => {}, // Ignore whitespace
+            '\n' => self.line += 1,
+            '(' => self.add_token(TokenType::LeftParen, None),
+            ')' => self.add_token(TokenType::RightParen, None),
+            '{' => self.add_token(TokenType::LeftBrace, None),
+            '}' => self.add_token(TokenType::RightBrace, None),
+            '[' => self.add_token(TokenType::LeftBracket, None),
+            ']' => self.add_token(TokenType::RightBracket, None),
+            ',' => self.add_token(TokenType::Comma, None),
+            '.' => self.add_token(TokenType::Dot, None),
+            ':' => self.add_token(TokenType::Colon, None),
+            '-' => {
+                if self.match_char('>') {
+                    self.add_token(TokenType::Arrow, None);
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::MinusAssign, None);
+                } else {
+                    self.add_token(TokenType::Minus, None);
+                }
+            },
+            '+' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::PlusAssign, None);
+                } else {
+                    self.add_token(TokenType::Plus, None);
+                }
+            },
+            ';' => self.add_token(TokenType::Semicolon, None),
+            '*' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::MultiplyAssign, None);
+                } else {
+                    self.add_token(TokenType::Multiply, None);
+                }
+            },
+            '!' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::NotEqual
+                } else {
+                    TokenType::Not
+                };
+                self.add_token(token_type, None);
+            },
+            '=' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::Equal
+                } else {
+                    TokenType::Assign
+                };
+                self.add_token(token_type, None);
+            },
+            '<' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::LessEqual
+                } else {
+                    TokenType::Less
+                };
+                self.add_token(token_type, None);
+            },
+            '>' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::GreaterEqual
+                } else {
+                    TokenType::Greater
+                };
+                self.add_token(token_type, None);
+            },
+            '|' => {
+                if self.match_char('|') {
+                    self.add_token(TokenType::Or, None);
+                } else if self.match_char('>') {
+                    if self.match_char('>') {
+                        self.add_token(TokenType::PipeThread, None);
+                    } else {
+                        self.add_token(TokenType::PipeMap, None);
+                    }
+                } else if self.match_char('?') {
+                    self.add_token(TokenType::PipeFilter, None);
+                } else if self.match_char(':') {
+                    self.add_token(TokenType::PipeApply, None);
+                } else {
+                    return Err(RuntimeError::new(
+                        "Unexpected character: |".to_string(),
+                        Some(self.line),
+                        &self.filename,
+                    ).with_span(self.current_span()));
+                }
+            },
+            '/' => {
+                if self.match_char('/') {
+                    // A comment goes until the end of the line
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+                } else if self.match_char('*') {
+                    self.block_comment()?;
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::DivideAssign, None);
+                } else {
+                    self.add_token(TokenType::Divide, None);
+                }
+            },
+            '&' => {
+                if self.match_char('&') {
+                    self.add_token(TokenType::And, None);
+                } else {
+                    return Err(RuntimeError::new(
+                        "Unexpected character: &".to_string(),
+                        Some(self.line),
+                        &self.filename,
+                    ).with_span(self.current_span()));
+                }
+            },
+            '"' => self.string()?,
+            '\'' => {
+                let char_val = if self.peek() == '\\' {
+                    self.advance(); // The backslash
+                    self.escape_sequence()?
+                } else {
+                    self.advance()
+                };
+                if self.advance() != '\'' {
+                    return Err(RuntimeError::new(
+                        "Unterminated character literal.".to_string(),
+                        Some(self.line),
+                        &self.filename,
+                    ).with_span(self.current_span()));
+                }
+                self.add_token(TokenType::Char, Some(LiteralValue::Char(char_val)));
+            },
+            _ => {
+                if c.is_ascii_digit() {
+                    self.number()?;
+                } else if c.is_alphabetic() || c == '_' {
+                    self.identifier();
+                } else {
+                    return Err(RuntimeError::new(
+                        format!("Unexpected character: {}", c),
+                        Some(self.line),
+                        &self.filename,
+                    ).with_span(self.current_span()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
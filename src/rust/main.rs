@@ -7,11 +7,15 @@ mod interpreter;
 mod ast;
 mod builtin;
 mod error;
+mod resolver;
+mod diagnostics;
 
-use lexer::Lexer;
+use lexer::{Lexer, TokenType};
 use parser::Parser;
 use interpreter::Interpreter;
 use error::RuntimeError;
+use resolver::Resolver;
+use notify::Watcher;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -19,8 +23,9 @@ fn main() {
     match args.len() {
         1 => run_prompt(),
         2 => run_file(&args[1]),
+        3 if args[1] == "--watch" => watch_file(&args[2]),
         _ => {
-            eprintln!("Usage: mini_script [script]");
+            eprintln!("Usage: mini_script [--watch] [script]");
             process::exit(64);
         }
     }
@@ -31,7 +36,10 @@ fn run(source: &str, filename: &str, interpreter: Option<&mut Interpreter>) -> R
     let tokens = lexer.scan_tokens()?;
 
     let mut parser = Parser::new(tokens, filename);
-    let statements = parser.parse()?;
+    let mut statements = parser.parse()?;
+
+    let mut resolver = Resolver::new(filename);
+    resolver.resolve(&mut statements)?;
 
     match interpreter {
         Some(interp) => {
@@ -77,32 +85,200 @@ fn run_file(path: &str) {
         .to_string();
 
     if let Err(e) = run(&source, &abs_path, None) {
-        println!("{}", e);
+        println!("{}", diagnostics::render(&source, &e));
         process::exit(1);
     }
 }
 
+/// Runs `path` once, returning the canonical paths of every file involved
+/// (the script itself plus every module it imported, per the dependency
+/// graph) so the caller can decide what to watch for changes.
+fn run_watched(path: &str) -> Vec<String> {
+    println!("Mini Script Language Interpreter");
+    println!("=================================");
+    println!("Executing: {}", path);
+    println!("---------------------------------");
+    println!();
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => {
+            println!("Error: File not found at {}", path);
+            return vec![path.to_string()];
+        }
+    };
+
+    let mut interpreter = Interpreter::new(path);
+    if let Err(e) = run(&source, path, Some(&mut interpreter)) {
+        println!("{}", diagnostics::render(&source, &e));
+    }
+
+    let mut watched: Vec<String> = interpreter
+        .module_dependencies()
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    watched.push(path.to_string());
+    watched.sort();
+    watched.dedup();
+    watched
+}
+
+fn watch_file(path: &str) {
+    let abs_path = std::fs::canonicalize(path)
+        .unwrap_or_else(|_| std::path::PathBuf::from(path))
+        .display()
+        .to_string();
+
+    let mut watched_paths = run_watched(&abs_path);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Failed to start file watcher: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let watch_all = |watcher: &mut notify::RecommendedWatcher, paths: &[String]| {
+        for watched_path in paths {
+            let _ = watcher.watch(std::path::Path::new(watched_path), notify::RecursiveMode::NonRecursive);
+        }
+    };
+    let unwatch_all = |watcher: &mut notify::RecommendedWatcher, paths: &[String]| {
+        for watched_path in paths {
+            let _ = watcher.unwatch(std::path::Path::new(watched_path));
+        }
+    };
+
+    watch_all(&mut watcher, &watched_paths);
+    println!("Watching {} file(s) for changes. Press Ctrl-C to stop.", watched_paths.len());
+
+    loop {
+        match rx.recv() {
+            Ok(Ok(_event)) => {
+                // Debounce a burst of editor writes (save, then metadata update, ...) into one re-run.
+                while rx.recv_timeout(std::time::Duration::from_millis(150)).is_ok() {}
+
+                unwatch_all(&mut watcher, &watched_paths);
+                println!();
+                println!("=================================");
+                println!("Change detected, re-running...");
+                watched_paths = run_watched(&abs_path);
+                watch_all(&mut watcher, &watched_paths);
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {}", e),
+            Err(_) => break,
+        }
+    }
+}
+
 fn run_prompt() {
-    println!("Mini Script REPL (type 'exit' to quit)");
+    println!("Mini Script REPL (Ctrl-D to exit)");
     let mut interpreter = Interpreter::new("<REPL>");
-    
-    loop {
-        print!("> ");
-        use std::io::{self, Write};
-        io::stdout().flush().unwrap();
-        
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => {
-                let line = input.trim();
-                if line.to_lowercase() == "exit" {
-                    break;
+
+    let mut editor = match rustyline::DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("Failed to start REPL: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let history_path = repl_history_path();
+    let _ = editor.load_history(&history_path);
+
+    'outer: loop {
+        let mut buffer = String::new();
+
+        loop {
+            let prompt = if buffer.is_empty() { "> " } else { "... " };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if buffer.is_empty() && line.trim().is_empty() {
+                        continue;
+                    }
+                    if !buffer.is_empty() {
+                        buffer.push('\n');
+                    }
+                    buffer.push_str(&line);
+
+                    // Keep reading while braces/parens/brackets are unbalanced,
+                    // so a function or loop body can span several lines.
+                    if unclosed_delimiters(&buffer) <= 0 {
+                        break;
+                    }
+                }
+                Err(rustyline::error::ReadlineError::Interrupted) => {
+                    buffer.clear();
+                    continue;
                 }
-                if let Err(e) = run(line, "<REPL>", Some(&mut interpreter)) {
-                    println!("{}", e);
+                Err(rustyline::error::ReadlineError::Eof) => break 'outer,
+                Err(e) => {
+                    eprintln!("Readline error: {}", e);
+                    break 'outer;
                 }
             }
-            Err(_) => break,
+        }
+
+        if buffer.trim().is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(buffer.as_str());
+
+        if let Err(e) = run_repl_line(&buffer, &mut interpreter) {
+            println!("{}", diagnostics::render(&buffer, &e));
+        }
+    }
+
+    let _ = editor.save_history(&history_path);
+}
+
+/// Counts unmatched `{`/`(`/`[` in `source` (negative once there are more
+/// closers than openers) so `run_prompt` knows when to keep prompting for
+/// more lines instead of running a half-finished block. Goes through the
+/// real lexer rather than a naive character scan so braces inside strings
+/// don't throw off the count; a lex error (e.g. an unterminated string)
+/// is left for the lexer to report again once the buffer is actually run.
+fn unclosed_delimiters(source: &str) -> i32 {
+    let mut lexer = Lexer::new(source, "<REPL>");
+    let tokens = match lexer.scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(_) => return 0,
+    };
+
+    let mut depth = 0i32;
+    for token in &tokens {
+        match token.token_type {
+            TokenType::LeftBrace | TokenType::LeftParen | TokenType::LeftBracket => depth += 1,
+            TokenType::RightBrace | TokenType::RightParen | TokenType::RightBracket => depth -= 1,
+            _ => {}
         }
     }
+    depth
+}
+
+/// Path to the persistent REPL history file, kept in the user's home
+/// directory (falling back to the current directory if `HOME` isn't set).
+fn repl_history_path() -> std::path::PathBuf {
+    match std::env::var("HOME") {
+        Ok(home) => std::path::PathBuf::from(home).join(".mini_script_history"),
+        Err(_) => std::path::PathBuf::from(".mini_script_history"),
+    }
+}
+
+fn run_repl_line(source: &str, interpreter: &mut Interpreter) -> Result<(), RuntimeError> {
+    let mut lexer = Lexer::new(source, "<REPL>");
+    let tokens = lexer.scan_tokens()?;
+
+    let mut parser = Parser::new(tokens, "<REPL>");
+    let mut statements = parser.parse()?;
+
+    let mut resolver = Resolver::new("<REPL>");
+    resolver.resolve(&mut statements)?;
+
+    interpreter.interpret_repl(&statements)
 }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use crate::ast::{Expr, Stmt};
+use crate::error::RuntimeError;
+use crate::lexer::Token;
+
+/// Walks a parsed program between `Parser::parse` and `Interpreter::interpret`,
+/// computing how many environment hops separate each variable reference from
+/// the scope that declares it and storing that count in the `depth` field on
+/// `Expr::Variable`/`Expr::Assign`. This lets the interpreter jump straight to
+/// the right scope instead of searching the environment chain at runtime, and
+/// fixes closures capturing the wrong binding when an enclosing scope later
+/// redeclares the same name.
+///
+/// The outermost program is deliberately resolved with an empty scope stack,
+/// so top-level declarations are never given a depth: they stay `None` and
+/// the interpreter searches for them along the environment chain from the
+/// current scope outward (which reaches a module's own top-level scope
+/// before `globals`).
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    filename: String,
+}
+
+impl Resolver {
+    pub fn new(filename: &str) -> Self {
+        Self {
+            scopes: Vec::new(),
+            filename: filename.to_string(),
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &mut [Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            self.resolve_stmt(statement)?;
+        }
+        Ok(())
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn resolve_local(&self, name: &Token) -> Option<usize> {
+        for (distance, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                return Some(distance);
+            }
+        }
+        None
+    }
+
+    fn resolve_function(&mut self, params: &[Token], body: &mut [Stmt]) -> Result<(), RuntimeError> {
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve(body)?;
+        self.end_scope();
+        Ok(())
+    }
+
+    fn resolve_stmt(&mut self, stmt: &mut Stmt) -> Result<(), RuntimeError> {
+        match stmt {
+            Stmt::Block { statements } => {
+                self.begin_scope();
+                self.resolve(statements)?;
+                self.end_scope();
+            }
+            Stmt::Expression { expression } => {
+                self.resolve_expr(expression)?;
+            }
+            Stmt::Print { expressions } => {
+                for expression in expressions {
+                    self.resolve_expr(expression)?;
+                }
+            }
+            Stmt::Function { name, params, body } => {
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(params, body)?;
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch)?;
+                }
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.resolve_expr(value)?;
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(body)?;
+            }
+            Stmt::DoWhile { body, condition } => {
+                self.resolve_stmt(body)?;
+                self.resolve_expr(condition)?;
+            }
+            Stmt::Loop { body } => {
+                self.resolve_stmt(body)?;
+            }
+            Stmt::Import { .. } => {
+                // Imported bindings are discovered dynamically by running the
+                // module, so the resolver can't predeclare their names; they
+                // fall back to a runtime lookup (depth stays `None`).
+            }
+            Stmt::Assert { condition, message, .. } => {
+                self.resolve_expr(condition)?;
+                self.resolve_expr(message)?;
+            }
+            Stmt::Var { name, initializer } => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer)?;
+                }
+                self.define(name);
+            }
+            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Struct { name, .. } => {
+                self.declare(name);
+                self.define(name);
+            }
+            Stmt::For { var, iterable, body } => {
+                self.resolve_expr(iterable)?;
+                self.begin_scope();
+                self.declare(var);
+                self.define(var);
+                self.resolve_stmt(body)?;
+                self.end_scope();
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_expr(&mut self, expr: &mut Expr) -> Result<(), RuntimeError> {
+        match expr {
+            Expr::Variable { name, depth } => {
+                if let Some(scope) = self.scopes.last() {
+                    if scope.get(&name.lexeme) == Some(&false) {
+                        return Err(self.error(name, "Can't read local variable in its own initializer."));
+                    }
+                }
+                *depth = self.resolve_local(name);
+            }
+            Expr::Assign { name, value, depth, .. } => {
+                self.resolve_expr(value)?;
+                *depth = self.resolve_local(name);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.resolve_expr(left)?;
+                self.resolve_expr(right)?;
+            }
+            Expr::Call { callee, arguments, .. } => {
+                self.resolve_expr(callee)?;
+                for argument in arguments {
+                    self.resolve_expr(argument)?;
+                }
+            }
+            Expr::Grouping { expression } => {
+                self.resolve_expr(expression)?;
+            }
+            Expr::Literal { .. } => {}
+            Expr::ListLiteral { elements } => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+            }
+            Expr::Get { object, index } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+            }
+            Expr::Set { object, index, value, .. } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+                self.resolve_expr(value)?;
+            }
+            Expr::Unary { right, .. } => {
+                self.resolve_expr(right)?;
+            }
+            Expr::Lambda { params, body } => {
+                self.resolve_function(params, body)?;
+            }
+            Expr::Field { object, .. } => {
+                self.resolve_expr(object)?;
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.resolve_expr(value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn error(&self, token: &Token, message: &str) -> RuntimeError {
+        RuntimeError::new(
+            format!("Resolve Error at '{}': {}", token.lexeme, message),
+            Some(token.line),
+            &self.filename,
+        )
+    }
+}